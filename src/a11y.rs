@@ -0,0 +1,100 @@
+use crate::minesweeper::{Coordinate, PlayResult};
+use crate::tile::Tile;
+
+// Text used by screen readers, via AccessKit, to describe the board.
+
+fn plural(count: u32, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, singular)
+    } else {
+        format!("{} {}", count, plural)
+    }
+}
+
+/// Row and column, counted from one
+pub fn describe_coordinate(coord: &Coordinate) -> String {
+    format!("row {}, column {}", coord.y + 1, coord.x + 1)
+}
+
+pub fn describe_tile(tile: &Tile) -> String {
+    match tile {
+        Tile::Unrevealed | Tile::Pressed => "unrevealed".to_string(),
+        Tile::Flagged => "flagged".to_string(),
+        Tile::Questioned => "question marked".to_string(),
+        Tile::Blank => "revealed, no adjacent mines".to_string(),
+        Tile::Numeral(n) => format!(
+            "revealed, {}",
+            plural(*n, "adjacent mine", "adjacent mines")
+        ),
+        Tile::Mine => "mine".to_string(),
+        Tile::Detonated => "detonated mine".to_string(),
+        Tile::Blasted => "exploded mine".to_string(),
+        Tile::FlagCorrect => "correctly flagged mine".to_string(),
+        Tile::MisFlagged => "incorrectly flagged".to_string(),
+    }
+}
+
+/// The accessible label of a square on the board. Squares hidden by fog of war
+/// are not described.
+pub fn describe_square(coord: &Coordinate, tile: &Tile, hidden: bool) -> String {
+    if hidden {
+        format!("{}, hidden", describe_coordinate(coord))
+    } else {
+        format!("{}, {}", describe_coordinate(coord), describe_tile(tile))
+    }
+}
+
+/// Announces the outcome of a play. `num_revealed` is how many squares the play
+/// uncovered.
+pub fn describe_play(coord: &Coordinate, result: &PlayResult, num_revealed: u32) -> String {
+    match result {
+        PlayResult::Flagged(true) => format!("Flagged {}", describe_coordinate(coord)),
+        PlayResult::Flagged(false) => format!("Unflagged {}", describe_coordinate(coord)),
+        PlayResult::Questioned(true) => {
+            format!("Question marked {}", describe_coordinate(coord))
+        }
+        PlayResult::Questioned(false) => format!("Unmarked {}", describe_coordinate(coord)),
+        PlayResult::Explosion(c) => format!("Mine detonated at {}", describe_coordinate(c)),
+        _ if num_revealed == 0 => "No change".to_string(),
+        _ => format!("Revealed {}", plural(num_revealed, "square", "squares")),
+    }
+}
+
+#[test]
+fn test_describe_square() {
+    let c = Coordinate { x: 4, y: 2 };
+    assert_eq!(
+        describe_square(&c, &Tile::Numeral(2), false),
+        "row 3, column 5, revealed, 2 adjacent mines"
+    );
+    assert_eq!(
+        describe_square(&c, &Tile::Numeral(1), false),
+        "row 3, column 5, revealed, 1 adjacent mine"
+    );
+    assert_eq!(
+        describe_square(&c, &Tile::Flagged, false),
+        "row 3, column 5, flagged"
+    );
+    assert_eq!(
+        describe_square(&c, &Tile::Flagged, true),
+        "row 3, column 5, hidden"
+    );
+}
+
+#[test]
+fn test_describe_play() {
+    let c = Coordinate { x: 0, y: 0 };
+    assert_eq!(
+        describe_play(&c, &PlayResult::CascadedReveal(vec![]), 12),
+        "Revealed 12 squares"
+    );
+    assert_eq!(describe_play(&c, &PlayResult::NoChange, 0), "No change");
+    assert_eq!(
+        describe_play(&c, &PlayResult::Flagged(true), 0),
+        "Flagged row 1, column 1"
+    );
+    assert_eq!(
+        describe_play(&c, &PlayResult::Explosion(Coordinate { x: 1, y: 1 }), 0),
+        "Mine detonated at row 2, column 2"
+    );
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use egui::{ecolor::Hsva, Color32};
+
+use crate::enums::AnimationSpeed;
+use crate::minesweeper::Coordinate;
+
+// Animations are purely visual. The game model is always updated immediately and
+// an animation only delays when a square is drawn in its new state, so it never
+// holds up the timer or input.
+
+/// Seconds between successive rings of a cascade
+const RIPPLE_STEP: f64 = 0.025;
+
+/// Seconds between each mine shown after a loss
+const MINE_STEP: f64 = 0.05;
+
+/// Longest the loss sequence may take, however many mines are on the board
+const MINE_SEQUENCE_MAX: f64 = 2.0;
+
+/// Seconds a square stays highlighted after it appears
+const FLASH_LENGTH: f64 = 0.25;
+
+const CELEBRATION_LENGTH: f64 = 2.5;
+
+#[derive(Debug, Clone, Default)]
+pub struct Animations {
+    /// When each delayed square is drawn in its new state
+    appear_at: HashMap<(u32, u32), f64>,
+    flash_length: f64,
+
+    /// Start and end times of the win celebration
+    celebration: Option<(f64, f64)>,
+}
+
+impl Animations {
+    pub fn is_running(&self, now: f64) -> bool {
+        self.appear_at.values().any(|t| now < t + self.flash_length)
+            || self.celebration.map(|(_, end)| now < end).unwrap_or(false)
+    }
+
+    /// Immediately finishes all animations
+    pub fn skip(&mut self) {
+        self.appear_at.clear();
+        self.celebration = None;
+    }
+
+    /// Staggers the squares uncovered by a play outward from `origin`, ordered by
+    /// their flood-fill distance through the uncovered area, which is crossed by
+    /// way of the board's `neighbors`. Any running animation is finished first.
+    pub fn ripple<F>(
+        &mut self,
+        speed: &AnimationSpeed,
+        origin: &Coordinate,
+        revealed: &[Coordinate],
+        now: f64,
+        neighbors: F,
+    ) where
+        F: Fn(&Coordinate) -> Vec<Coordinate>,
+    {
+        self.skip();
+        let Some(scale) = speed.time_scale() else {
+            return;
+        };
+        self.flash_length = FLASH_LENGTH * scale;
+        flood_distances(origin, revealed, neighbors)
+            .into_iter()
+            .for_each(|(c, d)| {
+                self.appear_at
+                    .insert((c.x, c.y), now + d as f64 * RIPPLE_STEP * scale);
+            });
+    }
+
+    /// Shows `squares` one at a time, nearest to the detonated square first
+    pub fn mine_sequence(
+        &mut self,
+        speed: &AnimationSpeed,
+        detonated_on: Option<&Coordinate>,
+        squares: &[Coordinate],
+        now: f64,
+    ) {
+        let Some(scale) = speed.time_scale() else {
+            return;
+        };
+        self.flash_length = FLASH_LENGTH * scale;
+
+        let mut ordered = squares.to_vec();
+        if let Some(d) = detonated_on {
+            ordered.sort_by(|a, b| a.distance(d).total_cmp(&b.distance(d)));
+        }
+        let step = MINE_STEP.min(MINE_SEQUENCE_MAX / ordered.len().max(1) as f64) * scale;
+        ordered.iter().enumerate().for_each(|(i, c)| {
+            self.appear_at
+                .insert((c.x, c.y), now + (i + 1) as f64 * step);
+        });
+    }
+
+    pub fn celebrate(&mut self, speed: &AnimationSpeed, now: f64) {
+        if let Some(scale) = speed.time_scale() {
+            self.celebration = Some((now, now + CELEBRATION_LENGTH * scale));
+        }
+    }
+
+    /// Whether a square is still drawn as it was before the animated play
+    pub fn is_pending(&self, x: u32, y: u32, now: f64) -> bool {
+        self.appear_at
+            .get(&(x, y))
+            .map(|t| now < *t)
+            .unwrap_or(false)
+    }
+
+    /// Strength, from 1.0 down to 0.0, of the highlight on a square that has just
+    /// appeared
+    pub fn flash(&self, x: u32, y: u32, now: f64) -> f32 {
+        match self.appear_at.get(&(x, y)) {
+            Some(t) if now >= *t && self.flash_length > 0.0 => {
+                (1.0 - (now - t) / self.flash_length).max(0.0) as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// The color washed over a square during the win celebration. A rainbow sweeps
+    /// diagonally across the board and fades out as the celebration ends.
+    pub fn celebration_color(&self, x: u32, y: u32, now: f64) -> Option<Color32> {
+        let (start, end) = self.celebration?;
+        if now < start || now >= end {
+            return None;
+        }
+        let t = ((now - start) / (end - start)) as f32;
+        let hue = ((x + y) as f32 * 0.05 - t * 3.0).rem_euclid(1.0);
+        Some(Hsva::new(hue, 0.8, 1.0, 0.45 * (1.0 - t)).into())
+    }
+}
+
+/// Breadth-first distances from `origin` through the given squares, each connected
+/// to its `neighbors`. Squares not connected to the origin are placed one step
+/// beyond the furthest.
+fn flood_distances<F>(
+    origin: &Coordinate,
+    squares: &[Coordinate],
+    neighbors: F,
+) -> Vec<(Coordinate, u32)>
+where
+    F: Fn(&Coordinate) -> Vec<Coordinate>,
+{
+    let area = squares
+        .iter()
+        .map(|c| (c.x, c.y))
+        .collect::<HashSet<(u32, u32)>>();
+
+    let mut distances: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut visited = HashSet::from([(origin.x, origin.y)]);
+    let mut queue = VecDeque::from([(origin.clone(), 0_u32)]);
+    while let Some((c, d)) = queue.pop_front() {
+        if area.contains(&(c.x, c.y)) {
+            distances.insert((c.x, c.y), d);
+        }
+        neighbors(&c).into_iter().for_each(|n| {
+            if area.contains(&(n.x, n.y)) && visited.insert((n.x, n.y)) {
+                queue.push_back((n, d + 1));
+            }
+        });
+    }
+
+    let furthest = distances.values().max().copied().unwrap_or(0);
+    squares
+        .iter()
+        .map(|c| {
+            let d = distances.get(&(c.x, c.y)).copied().unwrap_or(furthest + 1);
+            (c.clone(), d)
+        })
+        .collect()
+}
+
+#[test]
+fn test_flood_distances() {
+    use crate::minesweeper::{GameBoard, Neighborhood, Topology, Variant};
+
+    let origin = Coordinate { x: 0, y: 0 };
+    let squares = vec![
+        Coordinate { x: 0, y: 0 },
+        Coordinate { x: 1, y: 1 },
+        Coordinate { x: 2, y: 1 },
+        Coordinate { x: 3, y: 0 },
+        Coordinate { x: 7, y: 7 },
+    ];
+    let distances = |board: &GameBoard, squares: &[Coordinate]| {
+        flood_distances(&origin, squares, |c| board.neighbors(c))
+            .into_iter()
+            .map(|(_, d)| d)
+            .collect::<Vec<u32>>()
+    };
+    let mut board = GameBoard::new(8, 8);
+    assert_eq!(distances(&board, &squares), vec![0, 1, 2, 3, 4]);
+
+    // Cascades that wrap across an edge, or open on other neighborhoods, ripple
+    // along the board's own adjacency
+    board.variant.topology = Topology::Torus;
+    assert_eq!(distances(&board, &squares), vec![0, 1, 2, 3, 1]);
+    board.variant = Variant::new(Neighborhood::Knight, Topology::Bounded);
+    let jumps = [(0, 0), (1, 2), (3, 3), (2, 0)].map(Coordinate::from);
+    assert_eq!(distances(&board, &jumps), vec![0, 1, 2, 2]);
+}
+
+#[test]
+fn test_animation_timing() {
+    use crate::minesweeper::GameBoard;
+
+    let origin = Coordinate { x: 0, y: 0 };
+    let revealed = vec![Coordinate { x: 0, y: 0 }, Coordinate { x: 1, y: 0 }];
+
+    let mut a = Animations::default();
+    let board = GameBoard::new(8, 8);
+    let neighbors = |c: &Coordinate| board.neighbors(c);
+    a.ripple(&AnimationSpeed::Off, &origin, &revealed, 0.0, neighbors);
+    assert!(!a.is_running(0.0));
+    assert!(!a.is_pending(1, 0, 0.0));
+
+    a.ripple(&AnimationSpeed::Normal, &origin, &revealed, 0.0, neighbors);
+    assert!(a.is_running(0.0));
+    assert!(!a.is_pending(0, 0, 0.0));
+    assert!(a.is_pending(1, 0, 0.0));
+    assert!(!a.is_pending(1, 0, RIPPLE_STEP));
+    assert_eq!(a.flash(1, 0, RIPPLE_STEP), 1.0);
+    assert!(!a.is_running(10.0));
+
+    let mines = vec![Coordinate { x: 5, y: 5 }, Coordinate { x: 1, y: 1 }];
+    a.mine_sequence(&AnimationSpeed::Normal, Some(&origin), &mines, 0.0);
+    assert!(a.is_pending(5, 5, MINE_STEP));
+    assert!(!a.is_pending(1, 1, MINE_STEP));
+
+    a.skip();
+    assert!(!a.is_running(0.0));
+    assert!(!a.is_pending(5, 5, 0.0));
+}
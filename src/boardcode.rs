@@ -0,0 +1,312 @@
+use anyhow::{anyhow, Result};
+
+use crate::minesweeper::{Coordinate, GameBoard, Variant};
+
+/// Prefix of a board code, so the format can change without misreading old codes
+const CODE_VERSION: &str = "mor1";
+
+/// The largest board width or height a code may describe
+pub const MAX_DIMENSION: u32 = 100;
+
+/// Where a shared board's mines are
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardLayout {
+    /// Placed by the game's generator from a seed. The first click is needed to
+    /// reproduce the board, since the squares around it are kept clear.
+    Seed(u64),
+
+    /// Exactly these squares, in row order, are mined
+    Mines(Vec<bool>),
+}
+
+/// A board that can be shared as text, such as `mor1:16x16x40:s9edb1b9f:8,8` for a
+/// seeded board or `mor1:9x9x10:m0a12...:4,4` for an explicit layout. Boards other
+/// than the classic one end with their variant, as in `mor1:9x9x10:s1:4,4:hex`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardCode {
+    pub width: u32,
+    pub height: u32,
+    pub num_mines: u32,
+    pub layout: BoardLayout,
+
+    /// Where the game was started. A seeded code without one only shares the seed,
+    /// and the board is placed around wherever the player first clicks.
+    pub first_click: Option<Coordinate>,
+
+    pub variant: Variant,
+}
+
+impl BoardCode {
+    /// The code of a board. Boards the generator can reproduce from their seed and
+    /// first click are shared by seed; any others by their layout.
+    pub fn for_board(board: &GameBoard) -> Self {
+        let mines = board
+            .squares
+            .iter()
+            .map(|s| s.is_mine())
+            .collect::<Vec<bool>>();
+        let layout = if !board.is_populated {
+            BoardLayout::Seed(board.seed)
+        } else {
+            let regenerated = board.first_click.as_ref().and_then(|fc| {
+                let mut b = GameBoard::new(board.width, board.height);
+                b.seed = board.seed;
+                b.variant = board.variant;
+                b.populate_mines_around(board.num_mines, Some(fc.clone()))
+                    .ok()
+                    .map(|_| b)
+            });
+            match regenerated {
+                Some(b)
+                    if b.squares
+                        .iter()
+                        .map(|s| s.is_mine())
+                        .eq(mines.iter().copied()) =>
+                {
+                    BoardLayout::Seed(board.seed)
+                }
+                _ => BoardLayout::Mines(mines),
+            }
+        };
+
+        BoardCode {
+            width: board.width,
+            height: board.height,
+            num_mines: board.num_mines,
+            layout,
+            first_click: board.first_click.clone(),
+            variant: board.variant,
+        }
+    }
+
+    pub fn to_code(&self) -> String {
+        let layout = match &self.layout {
+            BoardLayout::Seed(seed) => format!("s{:x}", seed),
+            BoardLayout::Mines(mines) => format!("m{}", mines_to_hex(mines)),
+        };
+        let first_click = self
+            .first_click
+            .as_ref()
+            .map(|c| format!("{},{}", c.x, c.y))
+            .unwrap_or_default();
+        let variant = if self.variant.is_classic() {
+            String::new()
+        } else {
+            format!(":{}", self.variant.key())
+        };
+        format!(
+            "{}:{}x{}x{}:{}:{}{}",
+            CODE_VERSION, self.width, self.height, self.num_mines, layout, first_click, variant
+        )
+    }
+
+    pub fn from_code(code: &str) -> Result<Self> {
+        let parts = code.trim().split(':').collect::<Vec<&str>>();
+        let (version, size, layout, first_click, variant) = match parts.as_slice() {
+            [version, size, layout, first_click] => {
+                (version, size, layout, first_click, Variant::default())
+            }
+            [version, size, layout, first_click, variant] => (
+                version,
+                size,
+                layout,
+                first_click,
+                Variant::from_key(variant).ok_or(anyhow!("Unknown board variant '{}'", variant))?,
+            ),
+            _ => return Err(anyhow!("Not a board code")),
+        };
+        if *version != CODE_VERSION {
+            return Err(anyhow!("Unsupported board code version '{}'", version));
+        }
+
+        let size = size
+            .split('x')
+            .map(|v| v.parse::<u32>())
+            .collect::<Result<Vec<u32>, _>>()?;
+        let [width, height, num_mines] = size.as_slice() else {
+            return Err(anyhow!("Invalid board size"));
+        };
+        let (width, height, num_mines) = (*width, *height, *num_mines);
+        if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(anyhow!("Invalid board size {}x{}", width, height));
+        }
+        if num_mines >= width * height {
+            return Err(anyhow!("Too many mines for a {}x{} board", width, height));
+        }
+
+        let first_click = match first_click.split_once(',') {
+            Some((x, y)) => Some(Coordinate {
+                x: x.parse()?,
+                y: y.parse()?,
+            }),
+            None if first_click.is_empty() => None,
+            None => return Err(anyhow!("Invalid first click '{}'", first_click)),
+        };
+        if let Some(fc) = &first_click {
+            if fc.x >= width || fc.y >= height {
+                return Err(anyhow!("First click is off the board"));
+            }
+        }
+
+        let layout = match layout.split_at(layout.len().min(1)) {
+            ("s", seed) => BoardLayout::Seed(u64::from_str_radix(seed, 16)?),
+            ("m", hex) => {
+                let mines = hex_to_mines(hex, (width * height) as usize)?;
+                if mines.iter().filter(|m| **m).count() as u32 != num_mines {
+                    return Err(anyhow!("The layout doesn't have {} mines", num_mines));
+                }
+                if let Some(fc) = &first_click {
+                    if mines[(fc.y * width + fc.x) as usize] {
+                        return Err(anyhow!("The first click is on a mine"));
+                    }
+                }
+                BoardLayout::Mines(mines)
+            }
+            _ => return Err(anyhow!("Invalid board layout")),
+        };
+
+        // The mines of a seeded board are placed around wherever the first click
+        // turns out to be, so there must be room for them wherever that is
+        let area = width * height;
+        if matches!(layout, BoardLayout::Seed(_))
+            && first_click.is_none()
+            && num_mines + variant.neighborhood.clear_area().min(area) > area
+        {
+            return Err(anyhow!(
+                "Too many mines to keep the first click clear on a {}x{} board",
+                width,
+                height
+            ));
+        }
+
+        Ok(BoardCode {
+            width,
+            height,
+            num_mines,
+            layout,
+            first_click,
+            variant,
+        })
+    }
+}
+
+/// Packs the layout eight squares to a byte, first square in the high bit, as hex
+pub(crate) fn mines_to_hex(mines: &[bool]) -> String {
+    mines
+        .chunks(8)
+        .map(|chunk| {
+            let byte = chunk
+                .iter()
+                .enumerate()
+                .fold(0_u8, |b, (i, m)| if *m { b | (0x80 >> i) } else { b });
+            format!("{:02x}", byte)
+        })
+        .collect()
+}
+
+pub(crate) fn hex_to_mines(hex: &str, num_squares: usize) -> Result<Vec<bool>> {
+    if hex.len() != num_squares.div_ceil(8) * 2 || !hex.is_ascii() {
+        return Err(anyhow!("The layout is the wrong length"));
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()?;
+    Ok((0..num_squares)
+        .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+        .collect())
+}
+
+#[cfg(test)]
+fn mines(board: &GameBoard) -> Vec<bool> {
+    board.squares.iter().map(|s| s.is_mine()).collect()
+}
+
+#[test]
+fn test_seeded_board_code() -> Result<()> {
+    use crate::minesweeper::{Neighborhood, Topology};
+
+    // Not yet started: only the seed is shared
+    let board = GameBoard::new(16, 16);
+    let code = BoardCode::for_board(&board);
+    assert_eq!(code.layout, BoardLayout::Seed(board.seed));
+    assert_eq!(code.first_click, None);
+    let shared = GameBoard::from_board_code(&BoardCode::from_code(&code.to_code())?).unwrap();
+    assert!(!shared.is_populated);
+    assert_eq!(shared.seed, board.seed);
+
+    // Started: the same seed and first click give the same board
+    let mut board = GameBoard::new(16, 16);
+    board
+        .populate_mines_around(40, Some(Coordinate { x: 3, y: 5 }))
+        .unwrap();
+    let code = BoardCode::for_board(&board).to_code();
+    assert!(code.starts_with("mor1:16x16x40:s"));
+    assert!(code.ends_with(":3,5"));
+
+    let shared = GameBoard::from_board_code(&BoardCode::from_code(&code)?).unwrap();
+    assert!(shared.is_populated);
+    assert_eq!(mines(&shared), mines(&board));
+    assert_eq!(shared.first_click, Some(Coordinate { x: 3, y: 5 }));
+
+    // Hexagonal boards keep a different area clear, and say so in their code
+    let mut board = GameBoard::new(16, 16);
+    board.variant.neighborhood = Neighborhood::Hex;
+    board
+        .populate_mines_around(40, Some(Coordinate { x: 3, y: 5 }))
+        .unwrap();
+    let code = BoardCode::for_board(&board).to_code();
+    assert!(matches!(
+        BoardCode::for_board(&board).layout,
+        BoardLayout::Seed(_)
+    ));
+    assert!(code.ends_with(":3,5:hex"));
+    let shared = GameBoard::from_board_code(&BoardCode::from_code(&code)?).unwrap();
+    assert_eq!(shared.variant.neighborhood, Neighborhood::Hex);
+    assert_eq!(mines(&shared), mines(&board));
+    assert!(BoardCode::from_code("mor1:9x9x10:s1::octagon").is_err());
+
+    // A seeded board must leave room to keep the first click clear
+    assert!(BoardCode::from_code("mor1:9x9x80:s1:").is_err());
+    assert!(BoardCode::from_code("mor1:9x9x72:s1:").is_ok());
+    assert!(BoardCode::from_code("mor1:9x9x76:s1::orthogonal").is_ok());
+
+    // So do boards that wrap, but only those that can
+    board.variant = Variant::new(Neighborhood::Square, Topology::Torus);
+    assert!(BoardCode::for_board(&board)
+        .to_code()
+        .ends_with(":3,5:torus"));
+    assert!(BoardCode::from_code("mor1:9x9x10:s1::hex-torus").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_layout_board_code() -> Result<()> {
+    let mut layout = vec![false; 9 * 9];
+    [0, 1, 8, 40, 80].iter().for_each(|i| layout[*i] = true);
+    let board = GameBoard::new_with_layout(9, 9, &layout).unwrap();
+
+    let code = BoardCode::for_board(&board);
+    assert_eq!(code.layout, BoardLayout::Mines(layout.clone()));
+    assert_eq!(code.to_code(), "mor1:9x9x5:mc080000000800000000080:");
+
+    let shared = GameBoard::from_board_code(&BoardCode::from_code(&code.to_code())?).unwrap();
+    assert_eq!(mines(&shared), layout);
+    assert_eq!(shared.num_mines, 5);
+    assert_eq!(shared.get_square(1, 1).unwrap().numeral, 2);
+
+    // Malformed codes
+    [
+        "mor1:9x9x5:mc0800000008000000000:",
+        "mor1:9x9x4:mc080000000800000000080:",
+        "mor1:9x9x5:mc080000000800000000080:0,0",
+        "mor1:9x9x81:s1:",
+        "mor1:0x9x1:s1:",
+        "mor1:9x9x10:s1:9,0",
+        "mor2:9x9x10:s1:",
+        "9x9x10:s1",
+    ]
+    .iter()
+    .for_each(|c| assert!(BoardCode::from_code(c).is_err(), "{}", c));
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::boardcode::MAX_DIMENSION;
+use crate::minesweeper::{GameBoard, Marking};
+use crate::persist::write_atomic;
+
+/// The largest board file read, in bytes. The largest board is well under this in
+/// either format, so anything bigger isn't worth reading into memory.
+const MAX_FILE_SIZE: u64 = 64 * 1024;
+
+/// The board file formats, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardFormat {
+    /// Binary board files from Minesweeper X and Arbiter
+    Mbf,
+
+    /// Text as written by `GameBoard::print`
+    Text,
+}
+
+impl BoardFormat {
+    pub fn for_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("mbf") => Ok(BoardFormat::Mbf),
+            Some("txt") => Ok(BoardFormat::Text),
+            _ => Err(anyhow!("Board files must end in .mbf or .txt")),
+        }
+    }
+}
+
+/// Reads a board's layout from a file, ready to play with every square hidden
+pub fn load_board(path: &Path) -> Result<GameBoard> {
+    let mut board = read_board(path)?;
+    board.reset_existing();
+    board
+        .populate_numerals()
+        .map_err(|e| anyhow!("Invalid board file: {:?}", e))?;
+    Ok(board)
+}
+
+/// Reads a board from a file as it was saved. Text files may show squares revealed,
+/// flagged or questioned.
+pub fn read_board(path: &Path) -> Result<GameBoard> {
+    let format = BoardFormat::for_path(path)?;
+    let mut bytes = vec![];
+    std::fs::File::open(path)?
+        .take(MAX_FILE_SIZE + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_FILE_SIZE {
+        return Err(anyhow!("The board file is too large"));
+    }
+    let board = match format {
+        BoardFormat::Mbf => GameBoard::from_mbf(&bytes),
+        BoardFormat::Text => GameBoard::from_text(&String::from_utf8(bytes)?),
+    }
+    .map_err(|e| anyhow!("Invalid board file: {:?}", e))?;
+
+    if board.width > MAX_DIMENSION || board.height > MAX_DIMENSION {
+        return Err(anyhow!(
+            "Boards larger than {0}x{0} aren't supported",
+            MAX_DIMENSION
+        ));
+    }
+    if board.num_mines == 0 || board.num_mines >= board.width * board.height {
+        return Err(anyhow!("The board has no mines or no safe squares"));
+    }
+    Ok(board)
+}
+
+/// Writes a board's layout to a file. Text files show the board fully revealed.
+pub fn save_board(board: &GameBoard, path: &Path) -> Result<()> {
+    if !board.is_populated {
+        return Err(anyhow!("The mines aren't placed until the first click"));
+    }
+    let bytes = match BoardFormat::for_path(path)? {
+        BoardFormat::Mbf => board
+            .to_mbf()
+            .map_err(|e| anyhow!("Unable to write the board: {:?}", e))?,
+        BoardFormat::Text => revealed(board).to_text().into_bytes(),
+    };
+    write_atomic(path, &bytes)
+}
+
+/// A copy of the board with every square revealed and unmarked
+fn revealed(board: &GameBoard) -> GameBoard {
+    let mut board = board.clone();
+    board.squares.iter_mut().for_each(|s| {
+        s.is_revealed = true;
+        s.marking = Marking::None;
+    });
+    board
+}
+
+#[test]
+fn test_board_files() -> Result<()> {
+    let dir =
+        std::env::temp_dir().join(format!("minesofrust-boardfile-test-{}", std::process::id()));
+    let mut board = GameBoard::new_populated(16, 16, 40).unwrap();
+    board
+        .play(0, 0, crate::minesweeper::RevealType::Flag)
+        .unwrap();
+
+    ["board.mbf", "board.txt"].iter().try_for_each(|name| {
+        let path = dir.join(name);
+        save_board(&board, &path)?;
+        let loaded = load_board(&path)?;
+        assert_eq!(loaded.num_mines, 40);
+        assert!(loaded
+            .squares
+            .iter()
+            .zip(board.squares.iter())
+            .all(|(a, b)| a.is_mine() == b.is_mine() && a.numeral == b.numeral));
+        assert!(loaded
+            .squares
+            .iter()
+            .all(|s| !s.is_revealed && !s.is_flagged()));
+        Ok::<(), anyhow::Error>(())
+    })?;
+
+    assert!(save_board(&GameBoard::new(9, 9), &dir.join("empty.mbf")).is_err());
+    assert!(save_board(&board, &dir.join("board.png")).is_err());
+
+    // Oversized files aren't read
+    let huge = dir.join("huge.txt");
+    std::fs::write(&huge, " ".repeat(MAX_FILE_SIZE as usize + 1))?;
+    assert!(read_board(&huge).is_err());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
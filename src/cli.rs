@@ -0,0 +1,231 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::boardcode::BoardCode;
+use crate::boardfile;
+use crate::leader::LeaderBoards;
+use crate::minesweeper::GameBoard;
+use crate::render::{self, RenderOptions};
+use crate::replay::{Replay, ReplayOutcome};
+use crate::replaygif::{self, GifOptions};
+use crate::theme::Palette;
+use crate::video;
+
+const USAGE: &str = "Usage:
+    minesofrust                                Start the game
+    minesofrust leaderboard export <file>      Export the leaderboards to a .json or .csv file
+    minesofrust leaderboard import <file>      Merge leaderboards from a .json or .csv file
+    minesofrust video stats <file>             Analyse a .rawvf or .avf video from another client
+    minesofrust render <board> <image.png> [--mines] [--size <pixels>] [--theme <name>]
+                                               Draw a board to a PNG image. The board is a
+                                               .txt, .mbf, .rawvf or .avf file, a board code
+                                               or a replay code.
+    minesofrust gif <replay> <image.gif> [--speed <x>] [--max-size <w>x<h>] [--no-cursor]
+                    [--no-timer] [--size <pixels>] [--theme <name>]
+                                               Animate a replay code, or a .rawvf or .avf
+                                               video, as a GIF.
+";
+
+/// Runs a command given on the command line instead of starting the game
+pub fn run(args: &[String]) -> Result<()> {
+    match args
+        .iter()
+        .map(|a| a.as_str())
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        ["leaderboard", "export", path] => {
+            load_leaderboards()?.export_to_file(Path::new(path))?;
+            println!("Exported leaderboards to {}", path);
+            Ok(())
+        }
+        ["leaderboard", "import", path] => {
+            let imported = LeaderBoards::import_from_file(Path::new(path))?;
+            let mut leaderboards = load_leaderboards()?;
+            let added = leaderboards.merge(&imported);
+            leaderboards.save()?;
+            println!("Imported {} new entries from {}", added, path);
+            Ok(())
+        }
+        ["video", "stats", path] => print_video_stats(&video::load_video(Path::new(path))?),
+        ["render", board, image, options @ ..] => {
+            let options = render_options(options)?;
+            render::save_png(&board_to_render(board)?, Path::new(image), options)?;
+            println!("Rendered {} to {}", board, image);
+            Ok(())
+        }
+        ["gif", source, image, options @ ..] => {
+            let options = gif_options(options)?;
+            replaygif::save_gif(&replay_to_animate(source)?, Path::new(image), &options)?;
+            println!("Animated {} to {}", source, image);
+            Ok(())
+        }
+        ["help"] | ["--help"] | ["-h"] => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(anyhow!("Unrecognized command\n\n{}", USAGE)),
+    }
+}
+
+fn print_video_stats(video: &video::Video) -> Result<()> {
+    let replay = &video.replay;
+    let stats = replay.analyse()?;
+    if let Some(player) = &video.player {
+        println!("Player:    {}", player);
+    }
+    if let Some(program) = &video.program {
+        println!("Program:   {}", program);
+    }
+    println!(
+        "Board:     {}x{} with {} mines",
+        replay.width, replay.height, replay.num_mines
+    );
+    println!(
+        "Outcome:   {}",
+        match stats.outcome {
+            ReplayOutcome::Won => "Won",
+            ReplayOutcome::Lost => "Lost",
+            ReplayOutcome::Unfinished => "Unfinished",
+        }
+    );
+    println!("Time:      {:.2}", stats.time);
+    println!("3BV:       {}", stats.three_bv);
+    println!("Clicks:    {}", stats.clicks);
+    println!("Flags:     {}", stats.flags_used);
+    if let Some(rate) = stats.three_bv_per_second() {
+        println!("3BV/s:     {:.3}", rate);
+    }
+    if let Some(ioe) = stats.ioe() {
+        println!("IOE:       {:.3}", ioe);
+    }
+    Ok(())
+}
+
+fn render_options(args: &[&str]) -> Result<RenderOptions> {
+    let mut options = RenderOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !render_option(&mut options, arg, &mut args)? {
+            return Err(anyhow!("Unrecognized option '{}'\n\n{}", arg, USAGE));
+        }
+    }
+    Ok(options)
+}
+
+fn gif_options(args: &[&str]) -> Result<GifOptions> {
+    let mut options = GifOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--speed" => {
+                options.speed = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or(anyhow!("--speed needs a number"))?;
+            }
+            "--max-size" => {
+                (options.max_width, options.max_height) = args
+                    .next()
+                    .and_then(|s| s.split_once('x'))
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .ok_or(anyhow!("--max-size needs a size such as 640x480"))?;
+            }
+            "--no-cursor" => options.cursor = false,
+            "--no-timer" => options.timer = false,
+            _ => {
+                if !render_option(&mut options.render, arg, &mut args)? {
+                    return Err(anyhow!("Unrecognized option '{}'\n\n{}", arg, USAGE));
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Applies an option for drawing the board, if `arg` is one, taking its value from
+/// `args`
+fn render_option<'a>(
+    options: &mut RenderOptions,
+    arg: &str,
+    args: &mut impl Iterator<Item = &'a &'a str>,
+) -> Result<bool> {
+    match arg {
+        "--mines" => options.show_mines = true,
+        "--size" => {
+            options.square_size = args
+                .next()
+                .and_then(|s| s.parse().ok())
+                .ok_or(anyhow!("--size needs a number of pixels"))?;
+        }
+        "--theme" => {
+            let name = args.next().ok_or(anyhow!("--theme needs a name"))?;
+            options.palette = palette_named(name)?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// A built-in or custom theme, by its name in the Options window
+fn palette_named(name: &str) -> Result<Palette> {
+    [
+        Palette::light(),
+        Palette::dark(),
+        Palette::deuteranopia(),
+        Palette::protanopia(),
+        Palette::high_contrast(),
+    ]
+    .into_iter()
+    .chain(Palette::load_custom_themes())
+    .find(|p| p.name.eq_ignore_ascii_case(name))
+    .ok_or(anyhow!("Unknown theme '{}'", name))
+}
+
+/// The board to draw from a file or code. Videos and replays are drawn as they
+/// were left at the end of the game.
+fn board_to_render(source: &str) -> Result<GameBoard> {
+    let path = Path::new(source);
+    if path.exists() {
+        match video::VideoFormat::for_path(path) {
+            Ok(_) => Ok(video::load_video(path)?.replay.play_out()?.0),
+            Err(_) => boardfile::read_board(path),
+        }
+    } else if let Ok(replay) = Replay::from_code(source) {
+        Ok(replay.play_out()?.0)
+    } else {
+        let code = BoardCode::from_code(source)
+            .map_err(|e| anyhow!("'{}' isn't a board file or code: {}", source, e))?;
+        let board = GameBoard::from_board_code(&code)
+            .map_err(|e| anyhow!("Invalid board code: {:?}", e))?;
+        if board.is_populated {
+            Ok(board)
+        } else {
+            Err(anyhow!(
+                "The board code has no first click, so no mines yet"
+            ))
+        }
+    }
+}
+
+/// The replay to animate from a replay code or a video file
+fn replay_to_animate(source: &str) -> Result<Replay> {
+    let path = Path::new(source);
+    if path.exists() {
+        Ok(video::load_video(path)?.replay)
+    } else {
+        Replay::from_code(source)
+            .map_err(|e| anyhow!("'{}' isn't a video file or a replay code: {}", source, e))
+    }
+}
+
+/// Loads the saved leaderboards. Unlike the game, this fails rather than falling
+/// back to empty leaderboards when the file can't be read.
+fn load_leaderboards() -> Result<LeaderBoards> {
+    if LeaderBoards::file_path()?.exists() {
+        LeaderBoards::load()
+    } else {
+        Ok(LeaderBoards::default())
+    }
+}
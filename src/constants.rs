@@ -1,5 +1,3 @@
-use egui::Color32;
-
 pub const DEFAULT_BEGINNER_WIDTH: u32 = 9;
 pub const DEFAULT_BEGINNER_HEIGHT: u32 = 9;
 pub const DEFAULT_BEGINNER_NUM_MINES: u32 = 10;
@@ -18,9 +16,8 @@ pub const DEFAULT_EXPERT_NUM_MINES: u32 = 80;
 pub const DEFAULT_EXPERT_UI_WIDTH: f32 = 1215.0;
 pub const DEFAULT_EXPERT_UI_HEIGHT: f32 = 885.0;
 
-#[allow(dead_code)]
-pub const COLOR_UNREVEALED: Color32 = Color32::from_rgb(0, 92, 128);
-pub const COLOR_BORDER: Color32 = Color32::from_rgb(27, 27, 27);
-pub const COLOR_DETONATED: Color32 = Color32::GOLD;
-pub const COLOR_REVEALED: Color32 = Color32::DARK_GRAY;
-pub const COLOR_MISFLAGGED: Color32 = Color32::from_rgb(255, 188, 188);
+/// Window size of boards other than the difficulty levels: each square, plus the
+/// space around the board
+pub const UI_SQUARE_SIZE: f32 = 40.0;
+pub const UI_MARGIN_WIDTH: f32 = 15.0;
+pub const UI_MARGIN_HEIGHT: f32 = 245.0;
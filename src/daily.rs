@@ -0,0 +1,103 @@
+use chrono::{Datelike, Local, NaiveDate};
+
+use crate::enums::GameDifficulty;
+use crate::minesweeper::{Coordinate, Error, GameBoard};
+use crate::state::GameSettings;
+
+/// Every daily board is the same size, so that days can be compared
+pub const DAILY_DIFFICULTY: GameDifficulty = GameDifficulty::Intermediate;
+
+/// How dates are written as daily leaderboard keys
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The local date, which selects the daily board
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+pub fn date_key(date: &NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+pub fn parse_date_key(key: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(key, DATE_FORMAT).ok()
+}
+
+pub fn settings() -> GameSettings {
+    GameSettings::settings_for_difficulty(&DAILY_DIFFICULTY)
+}
+
+/// The seed of a day's board. This is SplitMix64 of the day number, which is fixed
+/// across platforms, unlike the standard library's hashers. The mines are then
+/// placed from the seed by `GameBoard`'s pinned generator, so everyone gets the
+/// same board on the same day as long as that placement doesn't change, which
+/// `test_daily_board` checks.
+pub fn seed_for(date: &NaiveDate) -> u64 {
+    let mut z = (date.num_days_from_ce() as u64).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// The centre square, which is kept clear of mines. Daily boards are populated
+/// before the first click so the layout is the same wherever it lands.
+pub fn safe_start(settings: &GameSettings) -> Coordinate {
+    Coordinate {
+        x: settings.width / 2,
+        y: settings.height / 2,
+    }
+}
+
+/// A day's board, with its mines already placed
+pub fn board_for(date: &NaiveDate) -> Result<GameBoard, Error> {
+    let settings = settings();
+    let mut board = GameBoard::new(settings.width, settings.height);
+    board.seed = seed_for(date);
+    board.populate_mines_around(settings.num_mines, Some(safe_start(&settings)))?;
+    board.populate_numerals()?;
+    Ok(board)
+}
+
+#[test]
+fn test_daily_board() {
+    let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let a = board_for(&date).unwrap();
+    let b = board_for(&date).unwrap();
+    let next_day = board_for(&date.succ_opt().unwrap()).unwrap();
+
+    let mines = |board: &GameBoard| {
+        board
+            .squares
+            .iter()
+            .map(|s| s.is_mine())
+            .collect::<Vec<bool>>()
+    };
+    assert_eq!(mines(&a), mines(&b));
+    assert_ne!(mines(&a), mines(&next_day));
+    assert_eq!(a.first_click, Some(safe_start(&settings())));
+    assert!(!a
+        .get_square_by_coordinate(&safe_start(&settings()))
+        .unwrap()
+        .is_mine());
+
+    // Neither the seed nor the layout may change, or past days' replays would no
+    // longer verify
+    assert_eq!(seed_for(&date), 0x9edb1b9f6f65e71e);
+    let layout = a
+        .squares
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_mine())
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+    assert_eq!(
+        layout,
+        [
+            9, 13, 20, 22, 25, 29, 32, 36, 47, 48, 52, 65, 69, 90, 97, 98, 100, 105, 106, 112, 124,
+            128, 143, 148, 164, 172, 174, 177, 185, 197, 199, 200, 215, 216, 227, 229, 230, 240,
+            248, 254
+        ]
+    );
+    assert_eq!(parse_date_key(&date_key(&date)), Some(date));
+    assert_eq!(date_key(&date), "2024-03-01");
+}
@@ -4,13 +4,23 @@ use serde::{Deserialize, Serialize};
 pub enum VisualTheme {
     Light,
     Dark,
+    Deuteranopia,
+    Protanopia,
+    HighContrast,
+    System,
+    Custom(String),
 }
 
 impl VisualTheme {
-    pub fn as_str(&self) -> &'static str {
-        match *self {
+    pub fn as_str(&self) -> &str {
+        match self {
             VisualTheme::Dark => "Dark",
             VisualTheme::Light => "Light",
+            VisualTheme::Deuteranopia => "Deuteranopia",
+            VisualTheme::Protanopia => "Protanopia",
+            VisualTheme::HighContrast => "High Contrast",
+            VisualTheme::System => "Follow System",
+            VisualTheme::Custom(name) => name,
         }
     }
 }
@@ -48,3 +58,65 @@ impl GameDifficulty {
         }
     }
 }
+
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone, Default)]
+pub enum TileRenderer {
+    #[default]
+    Images,
+    Vector,
+}
+
+impl TileRenderer {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TileRenderer::Images => "Images",
+            TileRenderer::Vector => "Vector",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone, Default)]
+pub enum InputScheme {
+    #[default]
+    Standard,
+    Classic,
+}
+
+impl InputScheme {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            InputScheme::Standard => "Standard",
+            InputScheme::Classic => "Classic (Left+Right Chord)",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone, Default)]
+pub enum AnimationSpeed {
+    #[default]
+    Off,
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            AnimationSpeed::Off => "Off",
+            AnimationSpeed::Slow => "Slow",
+            AnimationSpeed::Normal => "Normal",
+            AnimationSpeed::Fast => "Fast",
+        }
+    }
+
+    /// Multiplier applied to animation durations, or None if animations are off
+    pub fn time_scale(&self) -> Option<f64> {
+        match *self {
+            AnimationSpeed::Off => None,
+            AnimationSpeed::Slow => Some(2.0),
+            AnimationSpeed::Normal => Some(1.0),
+            AnimationSpeed::Fast => Some(0.4),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use egui::{vec2, Pos2, Rect, Vec2};
+use itertools::iproduct;
+
+use crate::minesweeper::Coordinate;
+
+/// Where the hexagons of a hexagonal board are drawn. The hexagons point up and
+/// down, and are laid out in rows with odd rows shifted right by half a hexagon,
+/// as `Neighborhood::Hex` expects.
+#[derive(Debug, Clone, Copy)]
+pub struct HexGrid {
+    /// The top left corner of the board
+    pub origin: Pos2,
+
+    /// The width of a hexagon, from one flat side to the other
+    pub size: f32,
+
+    /// The number of hexagons across and down the board
+    pub width: u32,
+    pub height: u32,
+}
+
+impl HexGrid {
+    /// The height of a hexagon, from point to point
+    fn hex_height(&self) -> f32 {
+        self.size * 2.0 / 3_f32.sqrt()
+    }
+
+    /// The distance between the centres of one row and the next. Each row overlaps
+    /// the points of the rows beside it.
+    fn row_step(&self) -> f32 {
+        self.hex_height() * 0.75
+    }
+
+    /// The size of the whole board
+    pub fn board_size(&self) -> Vec2 {
+        vec2(
+            (self.width as f32 + 0.5) * self.size,
+            self.height.saturating_sub(1) as f32 * self.row_step() + self.hex_height(),
+        )
+    }
+
+    pub fn center(&self, coord: &Coordinate) -> Pos2 {
+        let shift = if coord.y % 2 == 1 { 0.5 } else { 0.0 };
+        self.origin
+            + vec2(
+                (coord.x as f32 + 0.5 + shift) * self.size,
+                coord.y as f32 * self.row_step() + self.hex_height() / 2.0,
+            )
+    }
+
+    /// The corners of a hexagon, clockwise from the top
+    pub fn corners(&self, coord: &Coordinate) -> Vec<Pos2> {
+        let c = self.center(coord);
+        let (w, h) = (self.size / 2.0, self.hex_height() / 2.0);
+        vec![
+            c + vec2(0.0, -h),
+            c + vec2(w, -h / 2.0),
+            c + vec2(w, h / 2.0),
+            c + vec2(0.0, h),
+            c + vec2(-w, h / 2.0),
+            c + vec2(-w, -h / 2.0),
+        ]
+    }
+
+    /// The square a hexagon's tile is drawn in, as wide as the hexagon and centred
+    /// on it
+    pub fn tile_rect(&self, coord: &Coordinate) -> Rect {
+        Rect::from_center_size(self.center(coord), Vec2::splat(self.size))
+    }
+
+    /// The widest rectangle inside a hexagon, between its flat sides. Unlike the
+    /// tiles, these don't overlap the rows above and below, so each can be a
+    /// widget of its own.
+    pub fn input_rect(&self, coord: &Coordinate) -> Rect {
+        Rect::from_center_size(self.center(coord), vec2(self.size, self.hex_height() / 2.0))
+    }
+
+    /// The distance between the centres of two hexagons, in hexagons. Neighbors are
+    /// 1 apart.
+    pub fn distance(&self, a: &Coordinate, b: &Coordinate) -> f32 {
+        (self.center(a) - self.center(b)).length() / self.size
+    }
+
+    fn contains(&self, coord: &Coordinate, p: Pos2) -> bool {
+        in_hexagon(self.size, p - self.center(coord))
+    }
+
+    /// The hexagon under a point, if any. The hexagons tile the plane, so a point is
+    /// in whichever hexagon has the nearest centre, and that's one of those around
+    /// the row and column it's roughly in.
+    pub fn coordinate_at(&self, p: Pos2) -> Option<Coordinate> {
+        let offset = p - self.origin;
+        let row = ((offset.y - self.hex_height() / 2.0) / self.row_step()).round() as i64;
+        let col = (offset.x / self.size - 0.5).round() as i64;
+        iproduct!(row - 1..=row + 1, col - 1..=col + 1)
+            .filter(|(y, x)| {
+                *x >= 0 && *y >= 0 && *x < self.width as i64 && *y < self.height as i64
+            })
+            .map(|(y, x)| Coordinate {
+                x: x as u32,
+                y: y as u32,
+            })
+            .min_by(|a, b| {
+                let (da, db) = (self.center(a).distance(p), self.center(b).distance(p));
+                da.total_cmp(&db)
+            })
+            .filter(|c| self.contains(c, p))
+    }
+}
+
+/// Whether a point, given from the centre of a hexagon `size` wide, is inside it
+pub fn in_hexagon(size: f32, offset: Vec2) -> bool {
+    let (w, h) = (size / 2.0, size / 3_f32.sqrt());
+    offset.x.abs() <= w && offset.y.abs() <= h - offset.x.abs() * h / (2.0 * w)
+}
+
+#[test]
+fn test_hex_grid() {
+    let grid = HexGrid {
+        origin: Pos2::new(10.0, 20.0),
+        size: 40.0,
+        width: 4,
+        height: 3,
+    };
+    assert_eq!(grid.board_size().x, 180.0);
+
+    // Every hexagon is found at its centre and just inside its corners
+    iproduct!(0..3, 0..4).for_each(|(y, x)| {
+        let c = Coordinate { x, y };
+        assert_eq!(grid.coordinate_at(grid.center(&c)), Some(c.clone()));
+        grid.corners(&c).iter().for_each(|corner| {
+            let inside = *corner + (grid.center(&c) - *corner) * 0.05;
+            assert_eq!(grid.coordinate_at(inside), Some(c.clone()));
+        });
+    });
+
+    // The input rectangles are inside the hexagons, and don't overlap each other
+    iproduct!(0..3, 0..4).for_each(|(y, x)| {
+        let c = Coordinate { x, y };
+        let r = grid.input_rect(&c).shrink(0.01);
+        [
+            r.left_top(),
+            r.right_top(),
+            r.left_bottom(),
+            r.right_bottom(),
+        ]
+        .iter()
+        .for_each(|p| assert_eq!(grid.coordinate_at(*p), Some(c.clone())));
+        iproduct!(0..3, 0..4)
+            .filter(|(oy, ox)| (*ox, *oy) != (x, y))
+            .for_each(|(oy, ox)| {
+                assert!(!r.intersects(grid.input_rect(&Coordinate { x: ox, y: oy })));
+            });
+    });
+
+    // Odd rows are shifted right, so the rows interlock
+    assert_eq!(
+        grid.coordinate_at(grid.center(&Coordinate { x: 0, y: 1 }) + vec2(-21.0, 0.0)),
+        None
+    );
+    assert!((grid.distance(&(0, 0).into(), &(0, 1).into()) - 1.0).abs() < 1e-4);
+    assert!(grid.distance(&(0, 0).into(), &(1, 1).into()) > 1.5);
+
+    // Off the board
+    assert_eq!(grid.coordinate_at(Pos2::new(0.0, 0.0)), None);
+    assert_eq!(grid.coordinate_at(Pos2::new(500.0, 40.0)), None);
+}
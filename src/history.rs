@@ -0,0 +1,286 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::enums::{GameDifficulty, InputScheme};
+use crate::leader::as_df_date;
+use crate::minesweeper::{Neighborhood, Topology};
+use crate::persist::data_dir;
+use crate::state::{AppState, GameSettings};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Outcome {
+    Won,
+    Lost,
+    Abandoned,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Outcome::Won => "Won",
+            Outcome::Lost => "Lost",
+            Outcome::Abandoned => "Abandoned",
+        }
+    }
+}
+
+/// The board and the rules a game was played with
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct Ruleset {
+    pub difficulty: GameDifficulty,
+    pub width: u32,
+    pub height: u32,
+    pub num_mines: u32,
+    pub left_click_chord: bool,
+    pub fog_of_war: bool,
+    pub question_marks: bool,
+    pub input_scheme: InputScheme,
+
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+
+    #[serde(default)]
+    pub topology: Topology,
+}
+
+impl Ruleset {
+    pub fn new(state: &AppState, settings: &GameSettings) -> Self {
+        Ruleset {
+            difficulty: state.difficulty.clone(),
+            width: settings.width,
+            height: settings.height,
+            num_mines: settings.num_mines,
+            left_click_chord: state.left_click_chord,
+            fog_of_war: state.fog_of_war,
+            question_marks: state.question_marks,
+            input_scheme: state.input_scheme.clone(),
+            neighborhood: state.neighborhood,
+            topology: state.topology,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClickCounts {
+    pub reveals: u32,
+    pub chords: u32,
+    pub flags: u32,
+    pub total: u32,
+}
+
+/// One finished or abandoned game
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GameRecord {
+    pub ruleset: Ruleset,
+    pub seed: u64,
+
+    #[serde(with = "as_df_date")]
+    pub started: DateTime<FixedOffset>,
+
+    #[serde(with = "as_df_date")]
+    pub ended: DateTime<FixedOffset>,
+
+    /// Playing time in seconds, excluding time spent paused
+    pub duration: f64,
+    pub outcome: Outcome,
+    pub three_bv: u32,
+    pub clicks: ClickCounts,
+    pub percent_cleared: f64,
+
+    /// The board was replayed after being seen in an earlier attempt
+    pub assists_used: bool,
+}
+
+/// Selects records from the history. Unset fields match every record.
+#[derive(Clone, Default)]
+pub struct HistoryQuery {
+    pub difficulty: Option<GameDifficulty>,
+    pub outcome: Option<Outcome>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl HistoryQuery {
+    pub fn matches(&self, record: &GameRecord) -> bool {
+        self.difficulty
+            .as_ref()
+            .map(|d| *d == record.ruleset.difficulty)
+            .unwrap_or(true)
+            && self
+                .outcome
+                .as_ref()
+                .map(|o| *o == record.outcome)
+                .unwrap_or(true)
+            && self.since.map(|s| record.started >= s).unwrap_or(true)
+            && self.until.map(|u| record.started < u).unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistorySummary {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub abandoned: u32,
+    pub best_time: Option<f64>,
+    pub average_win_time: Option<f64>,
+}
+
+impl HistorySummary {
+    pub fn from_records(records: &[&GameRecord]) -> Self {
+        let count = |o: Outcome| records.iter().filter(|r| r.outcome == o).count() as u32;
+        let win_times = records
+            .iter()
+            .filter(|r| r.outcome == Outcome::Won)
+            .map(|r| r.duration)
+            .collect::<Vec<f64>>();
+
+        HistorySummary {
+            games: records.len() as u32,
+            wins: count(Outcome::Won),
+            losses: count(Outcome::Lost),
+            abandoned: count(Outcome::Abandoned),
+            best_time: win_times.iter().copied().min_by(|a, b| a.total_cmp(b)),
+            average_win_time: if win_times.is_empty() {
+                None
+            } else {
+                Some(win_times.iter().sum::<f64>() / win_times.len() as f64)
+            },
+        }
+    }
+}
+
+/// Every game played, kept as a JSON Lines file so that each game is a single
+/// appended line.
+#[derive(Clone, Default)]
+pub struct History {
+    pub records: Vec<GameRecord>,
+}
+
+impl History {
+    pub fn file_path() -> Result<PathBuf> {
+        data_dir()
+            .map(|d| d.join("history.jsonl"))
+            .ok_or(anyhow!("Unable to determine the platform directories"))
+    }
+
+    /// Parses JSON Lines, skipping any line that can't be read, such as one cut
+    /// short by a crash.
+    pub fn from_jsonl(s: &str) -> Self {
+        History {
+            records: s
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .filter_map(|l| match serde_json::from_str(l) {
+                    Ok(r) => Some(r),
+                    Err(why) => {
+                        println!("Skipping unreadable history record: {}", why);
+                        None
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = History::file_path()?;
+        if path.exists() {
+            Ok(History::from_jsonl(&std::fs::read_to_string(path)?))
+        } else {
+            Ok(History::default())
+        }
+    }
+
+    /// Adds a record and appends it to the history file
+    pub fn append(&mut self, record: GameRecord) -> Result<()> {
+        let line = serde_json::to_string(&record)?;
+        self.records.push(record);
+
+        let path = History::file_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(f, "{}", line)?;
+        Ok(())
+    }
+
+    /// The matching records, most recent first
+    pub fn query(&self, query: &HistoryQuery) -> Vec<&GameRecord> {
+        self.records
+            .iter()
+            .rev()
+            .filter(|r| query.matches(r))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+fn test_record(difficulty: GameDifficulty, outcome: Outcome, duration: f64) -> GameRecord {
+    let state = AppState {
+        difficulty: difficulty.clone(),
+        ..Default::default()
+    };
+    let now = chrono::Local::now().fixed_offset();
+    GameRecord {
+        ruleset: Ruleset::new(&state, &GameSettings::settings_for_difficulty(&difficulty)),
+        seed: 42,
+        started: now,
+        ended: now,
+        duration,
+        outcome,
+        three_bv: 30,
+        clicks: ClickCounts::default(),
+        percent_cleared: 100.0,
+        assists_used: false,
+    }
+}
+
+#[test]
+fn test_history_query() {
+    let history = History {
+        records: vec![
+            test_record(GameDifficulty::Beginner, Outcome::Won, 20.0),
+            test_record(GameDifficulty::Beginner, Outcome::Lost, 5.0),
+            test_record(GameDifficulty::Expert, Outcome::Won, 200.0),
+            test_record(GameDifficulty::Beginner, Outcome::Won, 10.0),
+            test_record(GameDifficulty::Beginner, Outcome::Abandoned, 1.0),
+        ],
+    };
+
+    let beginner = history.query(&HistoryQuery {
+        difficulty: Some(GameDifficulty::Beginner),
+        ..Default::default()
+    });
+    assert_eq!(beginner.len(), 4);
+    assert_eq!(beginner[0].outcome, Outcome::Abandoned);
+
+    let summary = HistorySummary::from_records(&beginner);
+    assert_eq!(summary.wins, 2);
+    assert_eq!(summary.losses, 1);
+    assert_eq!(summary.abandoned, 1);
+    assert_eq!(summary.best_time, Some(10.0));
+    assert_eq!(summary.average_win_time, Some(15.0));
+
+    let wins = history.query(&HistoryQuery {
+        outcome: Some(Outcome::Won),
+        ..Default::default()
+    });
+    assert_eq!(wins.len(), 3);
+}
+
+#[test]
+fn test_history_jsonl() -> Result<()> {
+    let record = test_record(GameDifficulty::Expert, Outcome::Lost, 12.5);
+    let jsonl = format!("{}\n{{\"truncated\n", serde_json::to_string(&record)?);
+    let history = History::from_jsonl(&jsonl);
+    assert_eq!(history.records.len(), 1);
+    assert_eq!(history.records[0].duration, 12.5);
+    assert!(history.records[0].ruleset == record.ruleset);
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use crate::minesweeper::{Coordinate, GameBoard};
+
+/// Tracks the Windows-classic gesture where pressing the primary and secondary
+/// mouse buttons together chords the square under the pointer.
+///
+/// The chord executes when the first of the two buttons is released. Every click
+/// egui reports from the moment both buttons went down until the last one is
+/// released is swallowed, so that the release never reveals or flags a square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ButtonChord {
+    #[default]
+    Idle,
+    /// Both buttons are being held down
+    Pressed,
+    /// The chord has executed, waiting for the remaining button to be released
+    Releasing,
+}
+
+/// What the board should do with the pointer input of the current frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ButtonChordFrame {
+    /// Execute a chord on the square under the pointer
+    pub execute: bool,
+
+    /// Ignore any reveal or flag clicks reported in this frame
+    pub suppress_clicks: bool,
+}
+
+impl ButtonChord {
+    /// Advances the gesture using the current state of the mouse buttons.
+    pub fn update(&mut self, primary_down: bool, secondary_down: bool) -> ButtonChordFrame {
+        let both_down = primary_down && secondary_down;
+        let any_down = primary_down || secondary_down;
+
+        let (next, execute) = match self {
+            ButtonChord::Idle if both_down => (ButtonChord::Pressed, false),
+            ButtonChord::Idle => (ButtonChord::Idle, false),
+            ButtonChord::Pressed if both_down => (ButtonChord::Pressed, false),
+            ButtonChord::Pressed if any_down => (ButtonChord::Releasing, true),
+            ButtonChord::Pressed => (ButtonChord::Idle, true),
+            ButtonChord::Releasing if any_down => (ButtonChord::Releasing, false),
+            ButtonChord::Releasing => (ButtonChord::Idle, false),
+        };
+
+        let suppress_clicks = *self != ButtonChord::Idle || next != ButtonChord::Idle;
+        *self = next;
+
+        ButtonChordFrame {
+            execute,
+            suppress_clicks,
+        }
+    }
+
+    /// Whether both buttons are currently held, i.e. the chord area should be drawn pressed.
+    pub fn is_pressed(&self) -> bool {
+        *self == ButtonChord::Pressed
+    }
+}
+
+/// Squares drawn as pressed while a mouse button is held over the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PressPreview {
+    /// A single square, for a pending reveal
+    Square(Coordinate),
+    /// A square and the squares around it, for a pending chord
+    Area(Coordinate),
+}
+
+impl PressPreview {
+    /// Whether a square is drawn pressed. Which squares are around another depends
+    /// on the board.
+    pub fn covers(&self, coord: &Coordinate, board: &GameBoard) -> bool {
+        match self {
+            PressPreview::Square(c) => c == coord,
+            PressPreview::Area(c) => board.is_near(c, coord),
+        }
+    }
+}
+
+#[test]
+fn test_button_chord_release_primary_first() {
+    let mut bc = ButtonChord::default();
+
+    // Primary goes down alone: a normal click in progress
+    assert_eq!(bc.update(true, false), ButtonChordFrame::default());
+
+    // Secondary joins in
+    let f = bc.update(true, true);
+    assert!(!f.execute);
+    assert!(f.suppress_clicks);
+    assert!(bc.is_pressed());
+
+    // Primary released: the chord fires, and the primary click is swallowed
+    let f = bc.update(false, true);
+    assert!(f.execute);
+    assert!(f.suppress_clicks);
+    assert!(!bc.is_pressed());
+
+    // Secondary released: must not be treated as a flag
+    let f = bc.update(false, false);
+    assert!(!f.execute);
+    assert!(f.suppress_clicks);
+
+    // Back to normal
+    assert_eq!(bc.update(false, false), ButtonChordFrame::default());
+    assert_eq!(bc, ButtonChord::Idle);
+}
+
+#[test]
+fn test_button_chord_release_both_together() {
+    let mut bc = ButtonChord::default();
+    bc.update(true, true);
+
+    let f = bc.update(false, false);
+    assert!(f.execute);
+    assert!(f.suppress_clicks);
+    assert_eq!(bc, ButtonChord::Idle);
+}
+
+#[test]
+fn test_press_preview_covers() {
+    let board = GameBoard::new(5, 5);
+    let single = PressPreview::Square(Coordinate { x: 2, y: 2 });
+    assert!(single.covers(&Coordinate { x: 2, y: 2 }, &board));
+    assert!(!single.covers(&Coordinate { x: 1, y: 2 }, &board));
+
+    let area = PressPreview::Area(Coordinate { x: 2, y: 2 });
+    assert!(area.covers(&Coordinate { x: 1, y: 1 }, &board));
+    assert!(area.covers(&Coordinate { x: 3, y: 3 }, &board));
+    assert!(!area.covers(&Coordinate { x: 4, y: 2 }, &board));
+
+    // On a hexagonal board, only six of the squares around are neighbors
+    let mut hex = GameBoard::new(5, 5);
+    hex.variant.neighborhood = crate::minesweeper::Neighborhood::Hex;
+    assert!(area.covers(&Coordinate { x: 1, y: 1 }, &hex));
+    assert!(!area.covers(&Coordinate { x: 3, y: 3 }, &hex));
+
+    // On a torus, the squares around reach over the edges
+    let mut torus = GameBoard::new(5, 5);
+    torus.variant.topology = crate::minesweeper::Topology::Torus;
+    let corner = PressPreview::Area(Coordinate { x: 0, y: 0 });
+    assert!(corner.covers(&Coordinate { x: 4, y: 4 }, &torus));
+    assert!(!corner.covers(&Coordinate { x: 4, y: 4 }, &board));
+}
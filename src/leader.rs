@@ -1,52 +1,397 @@
-use std::fs;
-use std::fs::File;
-use std::io::Write;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use anyhow::anyhow;
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::daily;
 use crate::enums::GameDifficulty;
+use crate::minesweeper::Variant;
+use crate::persist::*;
+use crate::replay::Replay;
+use crate::state::GameSettings;
 
 const MAX_ENTRIES_PER_BOARD: usize = 25;
 
+const MAX_PLAYER_NAME_LENGTH: usize = 100;
+
+/// A winning game. Entries recorded before the game tracked board statistics and
+/// rules have `None` for those fields, and only appear in the rankings by time.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Entry {
+    #[serde(default)]
     pub player_name: String,
 
-    #[serde(with = "as_df_date")]
+    #[serde(with = "as_df_date", default = "now")]
     pub date: DateTime<FixedOffset>,
     pub time: f64,
+
+    #[serde(default)]
+    pub three_bv: Option<u32>,
+
+    #[serde(default)]
+    pub clicks: Option<u32>,
+
+    #[serde(default)]
+    pub flags_used: Option<u32>,
+
+    #[serde(default)]
+    pub left_click_chord: Option<bool>,
+
+    #[serde(default)]
+    pub fog_of_war: Option<bool>,
+
+    /// The game's replay, as written by `Replay::to_code`. It's kept as text so an
+    /// unreadable replay only fails verification of its own entry.
+    #[serde(default)]
+    pub replay: Option<String>,
+
+    /// Whether the replay backs up the entry. Checked when the leaderboards are
+    /// loaded or added to, and never saved.
+    #[serde(skip)]
+    pub verification: Verification,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Verification {
+    #[default]
+    Unchecked,
+    Verified,
+    Failed(String),
+}
+
+/// The order in which a leaderboard is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ranking {
+    #[default]
+    Time,
+    ThreeBvPerSecond,
+    Ioe,
+}
+
+impl Ranking {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Ranking::Time => "Time",
+            Ranking::ThreeBvPerSecond => "3BV/s",
+            Ranking::Ioe => "IOE",
+        }
+    }
+
+    pub fn all() -> [Ranking; 3] {
+        [Ranking::Time, Ranking::ThreeBvPerSecond, Ranking::Ioe]
+    }
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Local::now().fixed_offset()
+}
+
+impl Entry {
+    pub fn new(player_name: &str, time: f64) -> Self {
+        Entry {
+            player_name: player_name.to_string(),
+            date: now(),
+            time,
+            three_bv: None,
+            clicks: None,
+            flags_used: None,
+            left_click_chord: None,
+            fog_of_war: None,
+            replay: None,
+            verification: Verification::Unchecked,
+        }
+    }
+
+    /// 3BV per second: how quickly the board's required clicks were made
+    pub fn three_bv_per_second(&self) -> Option<f64> {
+        (self.time.is_finite() && self.time > 0.0)
+            .then_some(self.three_bv)
+            .flatten()
+            .map(|b| b as f64 / self.time)
+    }
+
+    /// Index of efficiency: the board's 3BV over the clicks actually made
+    pub fn ioe(&self) -> Option<f64> {
+        match (self.three_bv, self.clicks) {
+            (Some(b), Some(c)) if c > 0 => Some(b as f64 / c as f64),
+            _ => None,
+        }
+    }
+
+    /// Won without placing a flag (NF)
+    pub fn is_no_flag(&self) -> bool {
+        self.flags_used == Some(0)
+    }
+
+    /// The value ranked on, where higher is better. None if the entry predates the
+    /// statistics the ranking needs.
+    fn score(&self, ranking: Ranking) -> Option<f64> {
+        match ranking {
+            Ranking::Time => Some(-self.time),
+            Ranking::ThreeBvPerSecond => self.three_bv_per_second(),
+            Ranking::Ioe => self.ioe(),
+        }
+    }
+
+    /// Short labels for the rules that differ from the classic game
+    pub fn rules_label(&self) -> String {
+        [
+            (self.left_click_chord == Some(true), "LC"),
+            (self.fog_of_war == Some(true), "FoW"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, l)| *l)
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
+
+    /// Whether two entries record the same game. Dates are compared to the
+    /// millisecond, the precision they are saved with.
+    pub fn same_as(&self, other: &Entry) -> bool {
+        self.player_name == other.player_name
+            && self.time == other.time
+            && self.date.timestamp_millis() == other.date.timestamp_millis()
+    }
+
+    /// Replays the game on a board of the level's size and kind, confirming the win,
+    /// the time and the board statistics.
+    pub fn verify(&self, settings: &GameSettings, variant: Variant) -> anyhow::Result<()> {
+        let replay = Replay::from_code(self.replay.as_ref().ok_or(anyhow!("No replay"))?)?;
+        if (replay.width, replay.height, replay.num_mines)
+            != (settings.width, settings.height, settings.num_mines)
+        {
+            return Err(anyhow!("The replay is of a different board size"));
+        }
+        if replay.variant != variant {
+            return Err(anyhow!(
+                "The replay is of a {} board",
+                replay.variant.as_str().to_lowercase()
+            ));
+        }
+        if replay.layout.is_some() {
+            // Only boards placed by the generator are ranked
+            return Err(anyhow!("The replay isn't of a generated board"));
+        }
+        replay.verify(self.time)?;
+
+        let three_bv = replay.board()?.three_bv();
+        if self.three_bv.is_some_and(|b| b != three_bv) {
+            Err(anyhow!("The 3BV doesn't match the board"))
+        } else if self.clicks.is_some_and(|c| c != replay.moves.len() as u32) {
+            Err(anyhow!("The clicks don't match the replay"))
+        } else if self.flags_used.is_some_and(|f| f != replay.flags_used()) {
+            Err(anyhow!("The flags used don't match the replay"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Verifies a daily entry, which must also have been played on that day's board
+    pub fn verify_daily(&self, date: &NaiveDate) -> anyhow::Result<()> {
+        self.verify(&daily::settings(), Variant::default())?;
+        let replay = Replay::from_code(self.replay.as_ref().ok_or(anyhow!("No replay"))?)?;
+        if replay.seed != daily::seed_for(date)
+            || replay.first_click != daily::safe_start(&daily::settings())
+        {
+            Err(anyhow!("The replay isn't of the daily board for {}", date))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.time.is_finite() || self.time <= 0.0 {
+            Err(anyhow!("Invalid time {}", self.time))
+        } else if self.player_name.trim().is_empty() {
+            Err(anyhow!("Missing player name"))
+        } else if self.player_name.chars().count() > MAX_PLAYER_NAME_LENGTH
+            || self.player_name.chars().any(|c| c.is_control())
+        {
+            Err(anyhow!("Invalid player name '{}'", self.player_name))
+        } else if self.clicks == Some(0) {
+            Err(anyhow!("Invalid click count 0"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A single leaderboard entry as a row of a CSV export
+#[derive(Deserialize, Serialize)]
+struct CsvRow {
+    difficulty: GameDifficulty,
+    player_name: String,
+    time: f64,
+
+    #[serde(with = "as_df_date")]
+    date: DateTime<FixedOffset>,
+
+    #[serde(default)]
+    three_bv: Option<u32>,
+
+    #[serde(default)]
+    clicks: Option<u32>,
+
+    #[serde(default)]
+    flags_used: Option<u32>,
+
+    #[serde(default)]
+    left_click_chord: Option<bool>,
+
+    #[serde(default)]
+    fog_of_war: Option<bool>,
+
+    #[serde(default)]
+    replay: Option<String>,
+
+    /// The date of a daily board entry, empty for the regular leaderboards
+    #[serde(default)]
+    daily: Option<String>,
+
+    /// The variant of a board other than the classic one, as written by
+    /// `Variant::key`
+    #[serde(default)]
+    variant: Option<String>,
 }
 
 #[derive(Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct LeaderBoard {
     pub entries: Vec<Entry>,
 }
 
 impl LeaderBoard {
-    pub fn add(&mut self, player_name: &str, time: f64) {
-        self.entries.push(Entry {
-            player_name: player_name.to_string(),
-            date: Local::now().fixed_offset(),
-            time,
+    pub fn add(&mut self, entry: Entry) {
+        self.entries.push(entry);
+        self.sort_and_trim();
+    }
+
+    /// Indices of the best entries under a ranking, best first
+    fn ranked_indices(&self, ranking: Ranking, no_flag: bool, verified_only: bool) -> Vec<usize> {
+        let mut scored = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !no_flag || e.is_no_flag())
+            .filter(|(_, e)| !verified_only || e.verification == Verification::Verified)
+            .filter_map(|(i, e)| e.score(ranking).map(|s| (s, i)))
+            .collect::<Vec<(f64, usize)>>();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored
+            .into_iter()
+            .take(MAX_ENTRIES_PER_BOARD)
+            .map(|(_, i)| i)
+            .collect()
+    }
+
+    /// The best entries under a ranking, optionally only those won without flags or
+    /// those whose replay has been verified
+    pub fn ranked(&self, ranking: Ranking, no_flag: bool, verified_only: bool) -> Vec<&Entry> {
+        self.ranked_indices(ranking, no_flag, verified_only)
+            .into_iter()
+            .map(|i| &self.entries[i])
+            .collect()
+    }
+
+    /// Adds the entries of `other` that aren't already present, returning how many
+    /// of them are still on the board once it's trimmed.
+    pub fn merge(&mut self, other: &LeaderBoard) -> usize {
+        let mut added = vec![];
+        other.entries.iter().for_each(|e| {
+            if !self.entries.iter().any(|x| x.same_as(e)) {
+                self.entries.push(e.clone());
+                added.push(e);
+            }
         });
         self.sort_and_trim();
+        self.entries
+            .iter()
+            .filter(|x| added.iter().any(|e| x.same_as(e)))
+            .count()
+    }
+
+    /// Drops the entries that fail validation, returning how many were dropped
+    pub fn retain_valid(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.validate().is_ok());
+        before - self.entries.len()
     }
 
+    /// Sorts the entries by time, keeping only those that place under at least one
+    /// of the rankings, with or without flags and with or without unverified entries.
     pub fn sort_and_trim(&mut self) {
-        self.entries.sort_by(|a, b| a.time.total_cmp(&b.time));
-        if self.entries.len() > MAX_ENTRIES_PER_BOARD {
-            self.entries = self.entries[0..MAX_ENTRIES_PER_BOARD].to_vec();
+        let keep = itertools::iproduct!(Ranking::all(), [false, true], [false, true])
+            .flat_map(|(r, no_flag, verified_only)| self.ranked_indices(r, no_flag, verified_only))
+            .collect::<HashSet<usize>>();
+        let mut entries = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| keep.contains(i))
+            .map(|(_, e)| e.clone())
+            .collect::<Vec<Entry>>();
+        entries.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.entries = entries;
+    }
+}
+
+/// One day's daily board
+#[derive(Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct DailyLeaderBoard {
+    /// Whether the ranked attempt at the board has been used. Only the first game
+    /// on a daily board is ranked; any after that are practice.
+    pub attempted: bool,
+    pub leaderboard: LeaderBoard,
+}
+
+/// The leaderboards of each level for another variant of the board, which are
+/// ranked apart from the classic game
+#[derive(Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct VariantLeaderBoards {
+    pub beginner: LeaderBoard,
+    pub intermediate: LeaderBoard,
+    pub expert: LeaderBoard,
+}
+
+impl VariantLeaderBoards {
+    fn level(&self, level: &GameDifficulty) -> &LeaderBoard {
+        match level {
+            GameDifficulty::Beginner => &self.beginner,
+            GameDifficulty::Intermediate => &self.intermediate,
+            GameDifficulty::Expert => &self.expert,
+        }
+    }
+
+    fn level_mut(&mut self, level: &GameDifficulty) -> &mut LeaderBoard {
+        match level {
+            GameDifficulty::Beginner => &mut self.beginner,
+            GameDifficulty::Intermediate => &mut self.intermediate,
+            GameDifficulty::Expert => &mut self.expert,
         }
     }
 }
 
 #[derive(Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct LeaderBoards {
     pub beginner: LeaderBoard,
     pub intermediate: LeaderBoard,
     pub expert: LeaderBoard,
+
+    /// Daily boards keyed by date, as written by `daily::date_key`
+    pub daily: BTreeMap<String, DailyLeaderBoard>,
+
+    /// Other variants of the board, keyed by `Variant::key`
+    pub variants: BTreeMap<String, VariantLeaderBoards>,
+}
+
+impl Versioned for LeaderBoards {
+    const VERSION: u32 = 1;
 }
 
 impl LeaderBoards {
@@ -60,46 +405,377 @@ impl LeaderBoards {
         .clone()
     }
 
-    pub fn add(&mut self, level: GameDifficulty, player_name: &str, time: f64) {
+    fn leaderboard_for_level_mut(&mut self, level: &GameDifficulty) -> &mut LeaderBoard {
         match level {
             GameDifficulty::Beginner => &mut self.beginner,
             GameDifficulty::Intermediate => &mut self.intermediate,
             GameDifficulty::Expert => &mut self.expert,
         }
-        .add(player_name, time);
     }
 
-    pub fn load_from_userhome() -> anyhow::Result<Self> {
-        let config_file_path = dirs::home_dir()
-            .unwrap()
-            .join(".apoapsys/minesofrust-leaderboard.toml");
+    /// The leaderboard of a level for a variant of the board
+    pub fn leaderboard_for(&self, variant: Variant, level: &GameDifficulty) -> LeaderBoard {
+        if variant.is_classic() {
+            self.leaderboard_for_level(level.clone())
+        } else {
+            self.variants
+                .get(&variant.key())
+                .map(|v| v.level(level).clone())
+                .unwrap_or_default()
+        }
+    }
+
+    fn leaderboard_for_mut(
+        &mut self,
+        variant: Variant,
+        level: &GameDifficulty,
+    ) -> &mut LeaderBoard {
+        if variant.is_classic() {
+            self.leaderboard_for_level_mut(level)
+        } else {
+            self.variants
+                .entry(variant.key())
+                .or_default()
+                .level_mut(level)
+        }
+    }
+
+    /// Every level's leaderboard, of the classic boards and then of each other
+    /// variant with leaderboards. Variants with an unknown key are skipped.
+    fn levels(&self) -> Vec<(Variant, GameDifficulty, &LeaderBoard)> {
+        let classic = Variant::default();
+        let mut levels = vec![
+            (classic, GameDifficulty::Beginner, &self.beginner),
+            (classic, GameDifficulty::Intermediate, &self.intermediate),
+            (classic, GameDifficulty::Expert, &self.expert),
+        ];
+        self.variants.iter().for_each(|(key, v)| {
+            if let Some(variant) = Variant::from_key(key) {
+                levels.extend([
+                    (variant, GameDifficulty::Beginner, &v.beginner),
+                    (variant, GameDifficulty::Intermediate, &v.intermediate),
+                    (variant, GameDifficulty::Expert, &v.expert),
+                ]);
+            }
+        });
+        levels
+    }
+
+    #[allow(dead_code)]
+    pub fn add(&mut self, level: GameDifficulty, player_name: &str, time: f64) {
+        self.add_entry(level, Entry::new(player_name, time));
+    }
+
+    pub fn add_entry(&mut self, level: GameDifficulty, entry: Entry) {
+        self.add_entry_for(Variant::default(), level, entry);
+    }
+
+    /// Adds a win on a variant of the board, ranked with others of its kind
+    pub fn add_entry_for(&mut self, variant: Variant, level: GameDifficulty, mut entry: Entry) {
+        entry.verification = verification(variant, &level, &entry);
+        self.leaderboard_for_mut(variant, &level).add(entry);
+    }
+
+    pub fn daily_attempted(&self, date: &NaiveDate) -> bool {
+        self.daily
+            .get(&daily::date_key(date))
+            .map(|d| d.attempted)
+            .unwrap_or(false)
+    }
+
+    /// Uses up the ranked attempt at a daily board
+    pub fn mark_daily_attempted(&mut self, date: &NaiveDate) {
+        self.daily
+            .entry(daily::date_key(date))
+            .or_default()
+            .attempted = true;
+    }
+
+    pub fn add_daily_entry(&mut self, date: &NaiveDate, mut entry: Entry) {
+        entry.verification = daily_verification(&daily::date_key(date), &entry);
+        self.daily
+            .entry(daily::date_key(date))
+            .or_default()
+            .leaderboard
+            .add(entry);
+    }
+
+    /// Checks the replay of every entry
+    pub fn verify_entries(&mut self) {
+        let levels = self
+            .levels()
+            .iter()
+            .map(|(v, level, _)| (*v, level.clone()))
+            .collect::<Vec<(Variant, GameDifficulty)>>();
+        levels.iter().for_each(|(v, level)| {
+            self.leaderboard_for_mut(*v, level)
+                .entries
+                .iter_mut()
+                .for_each(|e| e.verification = verification(*v, level, e));
+        });
+        self.daily.iter_mut().for_each(|(key, d)| {
+            d.leaderboard
+                .entries
+                .iter_mut()
+                .for_each(|e| e.verification = daily_verification(key, e));
+        });
+    }
+
+    /// Merges another set of leaderboards into this one, skipping entries already
+    /// present. Returns the number of entries added.
+    /// Daily entries are merged too, but not whether the other player used their
+    /// ranked attempts.
+    pub fn merge(&mut self, other: &LeaderBoards) -> usize {
+        let added: usize = other
+            .levels()
+            .iter()
+            .map(|(v, level, board)| self.leaderboard_for_mut(*v, level).merge(board))
+            .sum();
+        let added_daily: usize = other
+            .daily
+            .iter()
+            .map(|(key, d)| {
+                self.daily
+                    .entry(key.clone())
+                    .or_default()
+                    .leaderboard
+                    .merge(&d.leaderboard)
+            })
+            .sum();
+        added + added_daily
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.variants
+            .keys()
+            .try_for_each(|key| match Variant::from_key(key) {
+                Some(v) if !v.is_classic() => Ok(()),
+                _ => Err(anyhow!("Invalid board variant '{}'", key)),
+            })?;
+        self.levels().iter().try_for_each(|(v, level, board)| {
+            let name = if v.is_classic() {
+                level.as_str().to_string()
+            } else {
+                format!("{} {}", v.as_str(), level.as_str())
+            };
+            board.entries.iter().enumerate().try_for_each(|(i, e)| {
+                e.validate()
+                    .map_err(|why| anyhow!("{} entry {}: {}", name, i + 1, why))
+            })
+        })?;
+        self.daily.iter().try_for_each(|(key, d)| {
+            daily::parse_date_key(key).ok_or(anyhow!("Invalid daily board date '{}'", key))?;
+            d.leaderboard
+                .entries
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, e)| {
+                    e.validate()
+                        .map_err(|why| anyhow!("Daily {} entry {}: {}", key, i + 1, why))
+                })
+        })
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(s: &str) -> anyhow::Result<Self> {
+        let mut leaderboards: LeaderBoards = serde_json::from_str(s)?;
+        leaderboards.validate()?;
+        leaderboards.verify_entries();
+        leaderboards.sort_and_trim();
+        Ok(leaderboards)
+    }
+
+    pub fn to_csv(&self) -> anyhow::Result<String> {
+        let mut w = csv::Writer::from_writer(vec![]);
+        let boards = self
+            .levels()
+            .into_iter()
+            .map(|(v, level, board)| (v, level, None, board))
+            .chain(self.daily.iter().map(|(key, d)| {
+                (
+                    Variant::default(),
+                    daily::DAILY_DIFFICULTY,
+                    Some(key.clone()),
+                    &d.leaderboard,
+                )
+            }))
+            .collect::<Vec<(Variant, GameDifficulty, Option<String>, &LeaderBoard)>>();
+        boards.iter().try_for_each(|(v, level, daily, board)| {
+            board.entries.iter().try_for_each(|e| {
+                w.serialize(CsvRow {
+                    difficulty: level.clone(),
+                    player_name: e.player_name.clone(),
+                    time: e.time,
+                    date: e.date,
+                    three_bv: e.three_bv,
+                    clicks: e.clicks,
+                    flags_used: e.flags_used,
+                    left_click_chord: e.left_click_chord,
+                    fog_of_war: e.fog_of_war,
+                    replay: e.replay.clone(),
+                    daily: daily.clone(),
+                    variant: if v.is_classic() { None } else { Some(v.key()) },
+                })
+            })
+        })?;
+        Ok(String::from_utf8(w.into_inner()?)?)
+    }
+
+    pub fn from_csv(s: &str) -> anyhow::Result<Self> {
+        let mut leaderboards = LeaderBoards::default();
+        for row in csv::Reader::from_reader(s.as_bytes()).deserialize() {
+            let row: CsvRow = row?;
+            let variant = match &row.variant {
+                Some(key) => {
+                    Variant::from_key(key).ok_or(anyhow!("Invalid board variant '{}'", key))?
+                }
+                None => Variant::default(),
+            };
+            let board = match &row.daily {
+                Some(key) => {
+                    &mut leaderboards
+                        .daily
+                        .entry(key.clone())
+                        .or_default()
+                        .leaderboard
+                }
+                None => leaderboards.leaderboard_for_mut(variant, &row.difficulty),
+            };
+            board.entries.push(Entry {
+                player_name: row.player_name,
+                date: row.date,
+                time: row.time,
+                three_bv: row.three_bv,
+                clicks: row.clicks,
+                flags_used: row.flags_used,
+                left_click_chord: row.left_click_chord,
+                fog_of_war: row.fog_of_war,
+                replay: row.replay,
+                verification: Verification::Unchecked,
+            });
+        }
+        leaderboards.validate()?;
+        leaderboards.verify_entries();
+        leaderboards.sort_and_trim();
+        Ok(leaderboards)
+    }
+
+    /// Drops the entries that fail validation from every board, returning how many
+    /// were dropped. Unlike an import, which is refused outright, the saved
+    /// leaderboards keep whatever entries are still valid.
+    fn retain_valid(&mut self) -> usize {
+        let dropped = self.beginner.retain_valid()
+            + self.intermediate.retain_valid()
+            + self.expert.retain_valid();
+        let dropped_variants: usize = self
+            .variants
+            .values_mut()
+            .map(|v| {
+                v.beginner.retain_valid() + v.intermediate.retain_valid() + v.expert.retain_valid()
+            })
+            .sum();
+        let dropped_daily: usize = self
+            .daily
+            .values_mut()
+            .map(|d| d.leaderboard.retain_valid())
+            .sum();
+        dropped + dropped_variants + dropped_daily
+    }
+
+    fn sort_and_trim(&mut self) {
+        self.beginner.sort_and_trim();
+        self.intermediate.sort_and_trim();
+        self.expert.sort_and_trim();
+        self.variants.values_mut().for_each(|v| {
+            v.beginner.sort_and_trim();
+            v.intermediate.sort_and_trim();
+            v.expert.sort_and_trim();
+        });
+        self.daily
+            .values_mut()
+            .for_each(|d| d.leaderboard.sort_and_trim());
+    }
+
+    /// Writes the leaderboards to a file, as CSV if its extension is `.csv` and
+    /// JSON otherwise.
+    pub fn export_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        let s = if is_csv(path) {
+            self.to_csv()?
+        } else {
+            self.to_json()?
+        };
+        write_atomic(path, s.as_bytes())
+    }
+
+    /// Reads and validates leaderboards exported by `export_to_file`
+    pub fn import_from_file(path: &Path) -> anyhow::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        if is_csv(path) {
+            LeaderBoards::from_csv(&s)
+        } else {
+            LeaderBoards::from_json(&s)
+        }
+    }
+
+    pub fn file_path() -> anyhow::Result<PathBuf> {
+        resolve_path(
+            data_dir(),
+            "leaderboard.toml",
+            "minesofrust-leaderboard.toml",
+        )
+    }
+
+    pub fn load() -> anyhow::Result<Self> {
+        let config_file_path = LeaderBoards::file_path()?;
         if config_file_path.exists() {
             println!(
                 "Window state config file exists at path: {:?}",
                 config_file_path
             );
-            let t = std::fs::read_to_string(config_file_path)?;
-            Ok(toml::from_str(&t)?)
+            let mut leaderboards: LeaderBoards = load_versioned(&config_file_path)?;
+            let dropped = leaderboards.retain_valid();
+            if dropped > 0 {
+                println!("Dropped {} invalid leaderboard entries", dropped);
+            }
+            leaderboards.verify_entries();
+            Ok(leaderboards)
         } else {
             println!("Window state config file does not exist. Will be created on exit");
             Err(anyhow!("Config file does not exist"))
         }
     }
 
-    pub fn save_to_userhome(&self) {
-        let toml_str = toml::to_string(&self).unwrap();
-        let apoapsys_config_dir = dirs::home_dir().unwrap().join(".apoapsys/");
-        if !apoapsys_config_dir.exists() {
-            fs::create_dir(&apoapsys_config_dir).expect("Failed to create config directory");
-        }
-        let config_file_path = apoapsys_config_dir.join("minesofrust-leaderboard.toml");
-        let mut f = File::create(config_file_path).expect("Failed to create config file");
-        f.write_all(toml_str.as_bytes())
-            .expect("Failed to write to config file");
-        println!("{}", toml_str);
+    pub fn save(&self) -> anyhow::Result<()> {
+        save_versioned(self, &LeaderBoards::file_path()?)
     }
 }
 
+fn verification(variant: Variant, level: &GameDifficulty, entry: &Entry) -> Verification {
+    match entry.verify(&GameSettings::settings_for_difficulty(level), variant) {
+        Ok(()) => Verification::Verified,
+        Err(why) => Verification::Failed(why.to_string()),
+    }
+}
+
+fn daily_verification(key: &str, entry: &Entry) -> Verification {
+    let result = daily::parse_date_key(key)
+        .ok_or(anyhow!("Invalid daily board date '{}'", key))
+        .and_then(|date| entry.verify_daily(&date));
+    match result {
+        Ok(()) => Verification::Verified,
+        Err(why) => Verification::Failed(why.to_string()),
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false)
+}
+
 #[test]
 fn test_leaderboards() -> Result<(), anyhow::Error> {
     let mut leaderboard = LeaderBoards::default();
@@ -141,8 +817,8 @@ fn test_leaderboards() -> Result<(), anyhow::Error> {
         MAX_ENTRIES_PER_BOARD
     );
 
-    // leaderboard.save_to_userhome();
-    // let lb_reloaded = LeaderBoards::load_from_userhome()?;
+    // leaderboard.save()?;
+    // let lb_reloaded = LeaderBoards::load()?;
     // assert_eq!(
     //     lb_reloaded
     //         .leaderboard_for_level(GameDifficulty::Beginner)
@@ -157,11 +833,266 @@ fn test_leaderboards() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[test]
+fn test_leaderboards_export_import_merge() -> anyhow::Result<()> {
+    let mut a = LeaderBoards::default();
+    a.add(GameDifficulty::Beginner, "Ann, \"the quick\"", 12.5);
+    a.add(GameDifficulty::Expert, "Bob", 150.0);
+
+    // Both formats round trip, including names needing quoting
+    let from_json = LeaderBoards::from_json(&a.to_json()?)?;
+    let from_csv = LeaderBoards::from_csv(&a.to_csv()?)?;
+    [from_json, from_csv].iter().for_each(|l| {
+        assert!(l.beginner.entries[0].same_as(&a.beginner.entries[0]));
+        assert!(l.expert.entries[0].same_as(&a.expert.entries[0]));
+    });
+
+    // Merging identical entries adds nothing
+    let mut b = LeaderBoards::from_csv(&a.to_csv()?)?;
+    b.add(GameDifficulty::Beginner, "Cat", 9.0);
+    assert_eq!(a.merge(&b), 1);
+    assert_eq!(a.merge(&b), 0);
+    assert_eq!(a.beginner.entries.len(), 2);
+    assert_eq!(a.beginner.entries[0].player_name, "Cat");
+
+    // Entries trimmed from a full board aren't counted as added
+    let mut full = LeaderBoard::default();
+    (0..MAX_ENTRIES_PER_BOARD).for_each(|_| full.add(Entry::new("quick", 1.0)));
+    let mut slow = LeaderBoard::default();
+    slow.add(Entry::new("slow", 100.0));
+    slow.add(Entry::new("quicker", 0.5));
+    assert_eq!(full.merge(&slow), 1);
+    assert_eq!(full.entries.len(), MAX_ENTRIES_PER_BOARD);
+
+    // Invalid data is rejected
+    let csv = "difficulty,player_name,time,date\nBeginner,Dan,-1.0,2024-01-01T00:00:00.000 +0000\n";
+    assert!(LeaderBoards::from_csv(csv).is_err());
+    let csv = "difficulty,player_name,time,date\nHard,Dan,10.0,2024-01-01T00:00:00.000 +0000\n";
+    assert!(LeaderBoards::from_csv(csv).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_leaderboard_rankings() {
+    let entry = |name: &str, time: f64, three_bv: u32, clicks: u32, flags_used: u32| Entry {
+        three_bv: Some(three_bv),
+        clicks: Some(clicks),
+        flags_used: Some(flags_used),
+        ..Entry::new(name, time)
+    };
+
+    let mut board = LeaderBoard::default();
+    board.add(entry("lucky", 10.0, 10, 20, 5));
+    board.add(entry("fast", 20.0, 60, 80, 10));
+    board.add(entry("efficient", 30.0, 60, 60, 0));
+    board.add(Entry::new("legacy", 15.0));
+
+    let names = |r: Ranking, nf: bool| {
+        board
+            .ranked(r, nf, false)
+            .iter()
+            .map(|e| e.player_name.clone())
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(
+        names(Ranking::Time, false),
+        ["lucky", "legacy", "fast", "efficient"]
+    );
+    assert_eq!(
+        names(Ranking::ThreeBvPerSecond, false),
+        ["fast", "efficient", "lucky"]
+    );
+    assert_eq!(names(Ranking::Ioe, false), ["efficient", "fast", "lucky"]);
+    assert_eq!(names(Ranking::Time, true), ["efficient"]);
+
+    // Slow entries are kept while they still place under another ranking
+    (0..MAX_ENTRIES_PER_BOARD).for_each(|_| board.add(Entry::new("quick", 1.0)));
+    assert!(board.entries.iter().any(|e| e.player_name == "efficient"));
+    assert!(!board.entries.iter().any(|e| e.player_name == "legacy"));
+
+    // Invalid times have no rate, and are dropped from saved leaderboards
+    [0.0, -1.0, f64::NAN, f64::INFINITY].iter().for_each(|t| {
+        assert_eq!(entry("edited", *t, 60, 60, 0).three_bv_per_second(), None);
+    });
+    let mut leaderboards = LeaderBoards::default();
+    leaderboards
+        .beginner
+        .entries
+        .push(entry("edited", 0.0, 60, 60, 0));
+    leaderboards
+        .daily
+        .entry("2024-03-01".to_string())
+        .or_default()
+        .leaderboard
+        .entries
+        .push(entry("edited", f64::NAN, 60, 60, 0));
+    leaderboards
+        .intermediate
+        .add(entry("honest", 30.0, 60, 60, 0));
+    assert_eq!(leaderboards.retain_valid(), 2);
+    assert!(leaderboards.beginner.entries.is_empty());
+    assert_eq!(leaderboards.intermediate.entries.len(), 1);
+}
+
+#[test]
+fn test_leaderboard_verification() {
+    let replay = crate::replay::winning_replay(crate::replay::test_board());
+    let time = replay.moves.last().unwrap().time;
+    let entry = |name: &str, time: f64| Entry {
+        three_bv: Some(replay.board().unwrap().three_bv()),
+        clicks: Some(replay.moves.len() as u32),
+        flags_used: Some(0),
+        replay: Some(replay.to_code()),
+        ..Entry::new(name, time)
+    };
+
+    let mut leaderboards = LeaderBoards::default();
+    leaderboards.add_entry(GameDifficulty::Beginner, entry("honest", time));
+    leaderboards.add_entry(GameDifficulty::Beginner, entry("doctored", 1.0));
+    leaderboards.add_entry(GameDifficulty::Beginner, Entry::new("legacy", 2.0));
+    leaderboards.add_entry(GameDifficulty::Expert, entry("wrong board", time));
+
+    let names = |board: &LeaderBoard, verified_only: bool| {
+        board
+            .ranked(Ranking::Time, false, verified_only)
+            .iter()
+            .map(|e| e.player_name.clone())
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(
+        names(&leaderboards.beginner, false),
+        ["doctored", "legacy", "honest"]
+    );
+    assert_eq!(names(&leaderboards.beginner, true), ["honest"]);
+    assert!(names(&leaderboards.expert, true).is_empty());
+
+    // Verification is redone on import rather than trusted
+    let imported = LeaderBoards::from_csv(&leaderboards.to_csv().unwrap()).unwrap();
+    assert_eq!(names(&imported.beginner, true), ["honest"]);
+
+    // Statistics must match the replay too
+    let mut inflated = entry("inflated", time);
+    inflated.three_bv = Some(1000);
+    assert!(inflated
+        .verify(
+            &GameSettings::settings_for_difficulty(&GameDifficulty::Beginner),
+            Variant::default()
+        )
+        .is_err());
+}
+
+#[test]
+fn test_variant_leaderboards() {
+    use crate::minesweeper::{Coordinate, GameBoard, Neighborhood, Topology};
+
+    let hex = Variant::new(Neighborhood::Hex, Topology::Bounded);
+    let torus = Variant::new(Neighborhood::Square, Topology::Torus);
+    let entry = |name: &str, variant: Variant| {
+        let mut board = GameBoard::new(9, 9);
+        board.variant = variant;
+        board
+            .populate_mines_around(10, Some(Coordinate { x: 4, y: 4 }))
+            .unwrap();
+        board.populate_numerals().unwrap();
+        let replay = crate::replay::winning_replay(board);
+        Entry {
+            replay: Some(replay.to_code()),
+            ..Entry::new(name, replay.moves.last().unwrap().time)
+        }
+    };
+
+    // Each variant's wins are ranked on their own, and don't verify as any other
+    let mut leaderboards = LeaderBoards::default();
+    leaderboards.add_entry_for(hex, GameDifficulty::Beginner, entry("hex", hex));
+    leaderboards.add_entry_for(torus, GameDifficulty::Beginner, entry("torus", torus));
+    leaderboards.add_entry(GameDifficulty::Beginner, entry("misfiled", hex));
+    leaderboards.add_entry_for(torus, GameDifficulty::Beginner, entry("unwrapped", hex));
+    let board = leaderboards.leaderboard_for(hex, &GameDifficulty::Beginner);
+    assert_eq!(board.entries[0].verification, Verification::Verified);
+    assert!(matches!(
+        leaderboards.beginner.entries[0].verification,
+        Verification::Failed(_)
+    ));
+    let board = leaderboards.leaderboard_for(torus, &GameDifficulty::Beginner);
+    assert_eq!(
+        board
+            .ranked(Ranking::Time, false, true)
+            .iter()
+            .map(|e| e.player_name.clone())
+            .collect::<Vec<String>>(),
+        ["torus"]
+    );
+
+    // The variant survives export, and unknown ones are rejected
+    [
+        LeaderBoards::from_csv(&leaderboards.to_csv().unwrap()).unwrap(),
+        LeaderBoards::from_json(&leaderboards.to_json().unwrap()).unwrap(),
+    ]
+    .iter()
+    .for_each(|l| {
+        let board = l.leaderboard_for(hex, &GameDifficulty::Beginner);
+        assert_eq!(board.entries[0].player_name, "hex");
+        assert_eq!(board.entries[0].verification, Verification::Verified);
+        assert_eq!(
+            l.leaderboard_for(torus, &GameDifficulty::Beginner)
+                .entries
+                .len(),
+            2
+        );
+    });
+    let mut other = LeaderBoards::default();
+    assert_eq!(other.merge(&leaderboards), 4);
+    let csv = "difficulty,player_name,time,date,variant\nBeginner,Dan,10.0,2024-01-01T00:00:00.000 +0000,octagon\n";
+    assert!(LeaderBoards::from_csv(csv).is_err());
+}
+
+#[test]
+fn test_daily_leaderboard() {
+    let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let entry = |name: &str, replay: &Replay| Entry {
+        replay: Some(replay.to_code()),
+        ..Entry::new(name, replay.moves.last().unwrap().time)
+    };
+    let daily_replay = crate::replay::winning_replay(daily::board_for(&date).unwrap());
+    let next_day = date.succ_opt().unwrap();
+
+    let mut leaderboards = LeaderBoards::default();
+    assert!(!leaderboards.daily_attempted(&date));
+    leaderboards.mark_daily_attempted(&date);
+    assert!(leaderboards.daily_attempted(&date));
+    assert!(!leaderboards.daily_attempted(&next_day));
+
+    leaderboards.add_daily_entry(&date, entry("today", &daily_replay));
+    leaderboards.add_daily_entry(&next_day, entry("yesterday's board", &daily_replay));
+    let verification = |key: &str| {
+        leaderboards.daily[key].leaderboard.entries[0]
+            .verification
+            .clone()
+    };
+    assert_eq!(verification("2024-03-01"), Verification::Verified);
+    assert!(matches!(
+        verification("2024-03-02"),
+        Verification::Failed(_)
+    ));
+    assert!(leaderboards.beginner.entries.is_empty());
+
+    // Daily entries survive export and merge, but attempts aren't shared
+    let imported = LeaderBoards::from_csv(&leaderboards.to_csv().unwrap()).unwrap();
+    assert_eq!(imported.daily["2024-03-01"].leaderboard.entries.len(), 1);
+    let mut other = LeaderBoards::default();
+    assert_eq!(other.merge(&imported), 2);
+    assert!(!other.daily_attempted(&date));
+    assert_eq!(
+        other.daily["2024-03-01"].leaderboard.entries[0].verification,
+        Verification::Verified
+    );
+}
+
 pub mod as_df_date {
     use chrono::{DateTime, FixedOffset, Local};
     use serde::{self, Deserialize, Deserializer, Serializer};
 
-    const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f %z";
+    pub const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f %z";
 
     pub fn serialize<S>(date: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error>
     where
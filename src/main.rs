@@ -1,39 +1,71 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-use std::process;
-use std::time::SystemTime;
-
 use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
 use eframe::{egui, glow, Theme};
 use egui::{
-    Color32, Key, KeyboardShortcut, Modifiers, Pos2, RichText, Stroke, Vec2, ViewportCommand,
-    Visuals,
+    Color32, Key, KeyboardShortcut, Modifiers, Pos2, RichText, Stroke, ViewportCommand, Visuals,
 };
 use egui_extras::install_image_loaders;
 use itertools::iproduct;
 
+use animation::*;
 use enums::*;
+use history::*;
+use input::*;
 use minesweeper::*;
+use skin::*;
 use state::*;
+use textures::*;
+use theme::*;
+use tile::*;
+use timer::*;
 use toggle::*;
 
-use crate::leader::LeaderBoards;
-
+use crate::boardcode::BoardCode;
+use crate::hexgrid::HexGrid;
+use crate::leader::{Entry, LeaderBoard, LeaderBoards, Ranking, Verification};
+use crate::render::{wrap_marks, RenderOptions};
+use crate::replay::{Replay, ReplayMove};
+use crate::replaygif::GifOptions;
+
+mod a11y;
+mod animation;
+mod boardcode;
+mod boardfile;
+mod cli;
 mod constants;
+mod daily;
 mod enums;
+mod hexgrid;
+mod history;
+mod input;
 mod leader;
 mod minesweeper;
+mod persist;
+mod render;
+mod replay;
+mod replaygif;
+mod skin;
 mod state;
+mod textures;
+mod theme;
+mod tile;
+mod timer;
 mod toggle;
+mod vector;
+mod video;
 
 /// Settings as 'true' will allow the window to be resized and will print the dimensions to the console.
 const DBG_WINDOW_RESIZABLE: bool = false;
 
 #[derive(Debug, Clone, Default)]
 struct PlayEntry {
-    #[allow(dead_code)]
     coord: Coordinate,
     play_type: RevealType,
+
+    /// Seconds on the game timer when the play was made
+    time: f64,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -55,6 +87,18 @@ impl PlayList {
         self.list.len()
     }
 
+    /// The plays as moves of a replay
+    pub fn replay_moves(&self) -> Vec<ReplayMove> {
+        self.list
+            .iter()
+            .map(|e| ReplayMove {
+                time: e.time,
+                coord: e.coord.clone(),
+                play_type: e.play_type.clone(),
+            })
+            .collect()
+    }
+
     pub fn clicks(&self) -> u32 {
         self.list.len() as u32
     }
@@ -96,46 +140,91 @@ impl PlayList {
     }
 }
 
-fn now() -> f64 {
-    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-        Ok(n) => n.as_secs_f64(),
-        Err(_) => 0.0,
-    }
-}
-
 #[derive(Clone)]
 struct MinesOfRustApp {
     gameboard: GameBoard,
     state: AppState,
     image_loaders_installed: bool,
+    textures: Option<TileTextures>,
+    palette: Palette,
+    /// The theme setting and system dark mode the palette was built for
+    palette_source: Option<(VisualTheme, Option<bool>)>,
+    custom_themes: Vec<Palette>,
+    available_skins: Vec<String>,
     detonated_on: Option<Coordinate>,
     game_state: GameState,
-    game_started: f64,
-    game_finished: f64,
+    timer: GameTimer,
     game_settings: GameSettings,
     leaderboards: LeaderBoards,
     leaderboard_visible: bool,
     gamestats_visible: bool,
+    options_visible: bool,
     plays: PlayList,
     wins: u32,
     losses: u32,
+    button_chord: ButtonChord,
+    hovered_square: Option<Coordinate>,
+    announcement: String,
+    animations: Animations,
+    save_error: Option<String>,
+    history: History,
+    history_visible: bool,
+    history_query: HistoryQuery,
+    started_at: DateTime<FixedOffset>,
+    board_replayed: bool,
+    leaderboard_file: String,
+    leaderboard_status: String,
+    leaderboard_ranking: Ranking,
+    leaderboard_no_flag: bool,
+    leaderboard_verified_only: bool,
+
+    /// The date of the daily board being played, if it is one
+    daily: Option<NaiveDate>,
+
+    /// The daily game is the day's ranked attempt rather than practice
+    daily_ranked: bool,
+
+    /// The board was started from a pasted board code, so its wins aren't ranked
+    shared_board: bool,
+    board_code_visible: bool,
+    board_code_input: String,
+    board_code_status: String,
+    board_file: String,
+    gif_file: String,
+    gif_options: GifOptions,
 }
 
 fn main() -> Result<(), eframe::Error> {
-    let state = AppState::load_from_userhome().unwrap_or_default();
-    let leaderboards = LeaderBoards::load_from_userhome().unwrap_or_default();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        if let Err(why) = cli::run(&args) {
+            eprintln!("{}", why);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let state = AppState::load().unwrap_or_default();
+    let leaderboards = LeaderBoards::load().unwrap_or_default();
+    let history = History::load().unwrap_or_else(|why| {
+        println!("Failed to load game history: {}", why);
+        History::default()
+    });
     let settings = GameSettings::settings_for_difficulty(&state.difficulty);
+    let custom_themes = Palette::load_custom_themes();
+    let palette = Palette::for_theme(&state.theme, None, &custom_themes);
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_icon(load_icon())
-            .with_inner_size(Vec2::new(settings.ui_width, settings.ui_height))
+            .with_inner_size(settings.ui_size(state.neighborhood))
             .with_resizable(DBG_WINDOW_RESIZABLE),
         vsync: true,
         multisampling: 0,
         depth_buffer: 0,
         stencil_buffer: 0,
-        default_theme: if state.theme == VisualTheme::Dark {
+        follow_system_theme: true,
+        default_theme: if palette.dark {
             Theme::Dark
         } else {
             Theme::Light
@@ -143,21 +232,55 @@ fn main() -> Result<(), eframe::Error> {
         ..Default::default()
     };
 
+    let mut gameboard = GameBoard::new(settings.width, settings.height);
+    gameboard.variant = state.variant();
+    gameboard.use_question_marks = state.question_marks;
+    gameboard.question_blocks_reveal = state.question_blocks_reveal;
+
     let app = Box::new(MinesOfRustApp {
-        gameboard: GameBoard::new(settings.width, settings.height),
+        gameboard,
         state,
         image_loaders_installed: false,
+        textures: None,
+        palette,
+        palette_source: None,
+        custom_themes,
+        available_skins: vec![],
         detonated_on: None,
         game_state: GameState::NotStarted,
-        game_started: 0.0,
-        game_finished: 0.0,
+        timer: GameTimer::default(),
         game_settings: settings,
         leaderboards,
         leaderboard_visible: false,
         gamestats_visible: false,
+        options_visible: false,
         plays: PlayList::default(),
         wins: 0,
         losses: 0,
+        button_chord: ButtonChord::default(),
+        hovered_square: None,
+        announcement: String::new(),
+        animations: Animations::default(),
+        save_error: None,
+        history,
+        history_visible: false,
+        history_query: HistoryQuery::default(),
+        started_at: Local::now().fixed_offset(),
+        board_replayed: false,
+        leaderboard_file: String::new(),
+        leaderboard_status: String::new(),
+        leaderboard_ranking: Ranking::default(),
+        leaderboard_no_flag: false,
+        leaderboard_verified_only: false,
+        daily: None,
+        daily_ranked: false,
+        shared_board: false,
+        board_code_visible: false,
+        board_code_input: String::new(),
+        board_code_status: String::new(),
+        board_file: String::new(),
+        gif_file: String::new(),
+        gif_options: GifOptions::default(),
     });
 
     eframe::run_native("Mines of Rust", options, Box::new(|_cc| app))
@@ -188,12 +311,101 @@ impl eframe::App for MinesOfRustApp {
     }
 
     fn on_exit(&mut self, _gl: Option<&glow::Context>) {
-        self.state.save_to_userhome();
-        self.leaderboards.save_to_userhome();
+        self.record_abandoned_game();
+        self.save_all();
     }
 }
 
 impl MinesOfRustApp {
+    /// Writes the settings and leaderboards to disk
+    fn save_all(&mut self) {
+        self.save_settings();
+        self.save_leaderboards();
+    }
+
+    fn save_settings(&mut self) {
+        if let Err(why) = self.state.save() {
+            println!("Failed to save settings: {}", why);
+            self.save_error = Some(format!("Failed to save settings: {}", why));
+        }
+    }
+
+    fn save_leaderboards(&mut self) {
+        if let Err(why) = self.leaderboards.save() {
+            println!("Failed to save leaderboards: {}", why);
+            self.save_error = Some(format!("Failed to save leaderboards: {}", why));
+        }
+    }
+
+    fn save_error_ui(&mut self, ctx: &egui::Context) {
+        if let Some(why) = self.save_error.clone() {
+            egui::Window::new("Save Failed")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(why);
+                    if ui.button("OK").clicked() {
+                        self.save_error = None;
+                    }
+                });
+        }
+    }
+
+    /// Adds the current game to the history
+    fn record_game(&mut self, outcome: Outcome) {
+        let num_safe = self.gameboard.squares.len() as u32 - self.gameboard.num_mines;
+        let num_cleared = self
+            .gameboard
+            .squares
+            .iter()
+            .filter(|s| s.is_revealed && !s.is_mine())
+            .count() as u32;
+
+        let mut ruleset = Ruleset::new(&self.state, &self.game_settings);
+        ruleset.neighborhood = self.gameboard.variant.neighborhood;
+        ruleset.topology = self.gameboard.variant.topology;
+        if self.daily.is_some() {
+            ruleset.difficulty = daily::DAILY_DIFFICULTY;
+        }
+        let record = GameRecord {
+            ruleset,
+            seed: self.gameboard.seed,
+            started: self.started_at,
+            ended: Local::now().fixed_offset(),
+            duration: self.timer.elapsed().as_secs_f64(),
+            outcome,
+            three_bv: self.gameboard.three_bv(),
+            clicks: ClickCounts {
+                reveals: self.plays.reveals(),
+                chords: self.plays.chords(),
+                flags: self.plays.flagged(),
+                total: self.plays.clicks(),
+            },
+            percent_cleared: num_cleared as f64 / num_safe.max(1) as f64 * 100.0,
+            assists_used: self.board_replayed,
+        };
+        if let Err(why) = self.history.append(record) {
+            println!("Failed to save game history: {}", why);
+            self.save_error = Some(format!("Failed to save game history: {}", why));
+        }
+    }
+
+    /// Records a game in progress as abandoned, before it's replaced or the game exits
+    fn record_abandoned_game(&mut self) {
+        if self.game_state == GameState::Playing || self.game_state == GameState::Paused {
+            self.timer.stop();
+            self.record_game(Outcome::Abandoned);
+        }
+    }
+
+    /// Pauses the game, so the timer stops, and minimizes the window
+    fn boss_key(&mut self, ctx: &egui::Context) {
+        if self.game_state == GameState::Playing {
+            self.pause_game();
+        }
+        ctx.send_viewport_cmd(ViewportCommand::Minimized(true));
+    }
+
     fn update_difficulty_settings(&mut self) {
         self.game_settings = match self.state.difficulty {
             GameDifficulty::Beginner => GameSettings::beginner(),
@@ -204,26 +416,253 @@ impl MinesOfRustApp {
     }
 
     fn reset_new_game(&mut self, ctx: &egui::Context) -> Result<(), Error> {
+        self.record_abandoned_game();
+        let special_board = self.daily.take().is_some() | std::mem::take(&mut self.shared_board);
+        if special_board {
+            // Back to the chosen difficulty after a daily or shared board
+            self.update_difficulty_settings();
+        }
+        self.board_replayed = false;
         self.gameboard = GameBoard::new(self.game_settings.width, self.game_settings.height);
+        self.gameboard.variant = self.state.variant();
+        self.apply_marking_rules();
         self.plays.clear();
         self.game_state = GameState::NotStarted;
         self.detonated_on = None;
-        self.game_started = now();
+        self.timer.reset();
+        self.announcement = "New game".to_string();
+        self.animations.skip();
+
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(
+            self.game_settings
+                .ui_size(self.gameboard.variant.neighborhood),
+        ));
+
+        Ok(())
+    }
+
+    /// Starts today's daily board. The first game on it is ranked, and any after that
+    /// are practice.
+    fn reset_daily_game(&mut self, ctx: &egui::Context) -> Result<(), Error> {
+        self.reset_new_game(ctx)?;
+        let date = daily::today();
+        self.game_settings = daily::settings();
+        self.gameboard = daily::board_for(&date)?;
+        self.apply_marking_rules();
+        self.daily = Some(date);
+        self.daily_ranked = !self.leaderboards.daily_attempted(&date);
+        self.announcement = format!(
+            "Daily board for {}{}. The centre square is safe",
+            daily::date_key(&date),
+            if self.daily_ranked { "" } else { ", practice" }
+        );
+
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(
+            self.game_settings
+                .ui_size(self.gameboard.variant.neighborhood),
+        ));
+        Ok(())
+    }
 
-        ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2 {
-            x: self.game_settings.ui_width,
-            y: self.game_settings.ui_height,
-        }));
+    /// Starts the board described by a board code
+    fn start_shared_board(&mut self, ctx: &egui::Context, code: &str) -> Result<()> {
+        let code = BoardCode::from_code(code)?;
+        let board = GameBoard::from_board_code(&code)
+            .map_err(|e| anyhow::anyhow!("Unable to build the board: {:?}", e))?;
+        let announcement = match &code.first_click {
+            Some(c) => format!("Shared board. Start at {}", a11y::describe_coordinate(c)),
+            None => "Shared board".to_string(),
+        };
+        self.start_board(ctx, board, announcement)
+    }
 
+    /// Starts a board from a code or a file. Wins on these boards aren't ranked.
+    fn start_board(
+        &mut self,
+        ctx: &egui::Context,
+        board: GameBoard,
+        announcement: String,
+    ) -> Result<()> {
+        self.reset_new_game(ctx)
+            .map_err(|e| anyhow::anyhow!("Unable to reset the game: {:?}", e))?;
+        self.game_settings = GameSettings::for_board(board.width, board.height, board.num_mines);
+        self.gameboard = board;
+        self.apply_marking_rules();
+        self.shared_board = true;
+        self.announcement = announcement;
+
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(
+            self.game_settings
+                .ui_size(self.gameboard.variant.neighborhood),
+        ));
         Ok(())
     }
 
+    fn board_code_ui(&mut self, ctx: &egui::Context) {
+        let mut play = None;
+        let mut open = false;
+        let mut save_gif = false;
+        let code = BoardCode::for_board(&self.gameboard).to_code();
+        egui::Window::new("Board Code")
+            .open(&mut self.board_code_visible)
+            .show(ctx, |ui| {
+                ui.label("This board:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::Label::new(RichText::new(&code).monospace()).wrap(true));
+                    if ui.button("Copy").clicked() {
+                        ctx.output_mut(|o| o.copied_text = code.clone());
+                        self.board_code_status = "Copied to the clipboard".to_string();
+                    }
+                });
+                ui.separator();
+                ui.label("Play a shared board:");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.board_code_input);
+                    if ui.button("Play").clicked() {
+                        play = Some(self.board_code_input.clone());
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Board file (.mbf or .txt):");
+                    ui.text_edit_singleline(&mut self.board_file);
+                    let path = std::path::Path::new(&self.board_file);
+                    if ui.button("Save").clicked() {
+                        self.board_code_status = match boardfile::save_board(&self.gameboard, path)
+                        {
+                            Ok(()) => format!("Saved the board to {}", self.board_file),
+                            Err(why) => format!("Save failed: {}", why),
+                        };
+                    }
+                    if ui.button("Open").clicked() {
+                        open = true;
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("This game as a GIF:");
+                    ui.text_edit_singleline(&mut self.gif_file);
+                    if ui.button("Save").clicked() {
+                        save_gif = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Speed")
+                        .selected_text(format!("{}×", self.gif_options.speed))
+                        .show_ui(ui, |ui| {
+                            [0.5, 1.0, 2.0, 4.0].into_iter().for_each(|s| {
+                                ui.selectable_value(
+                                    &mut self.gif_options.speed,
+                                    s,
+                                    format!("{}×", s),
+                                );
+                            });
+                        });
+                    ui.checkbox(&mut self.gif_options.cursor, "Cursor");
+                    ui.checkbox(&mut self.gif_options.timer, "Timer");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Largest size:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.gif_options.max_width)
+                            .clamp_range(64..=render::MAX_IMAGE_SIZE),
+                    );
+                    ui.label("×");
+                    ui.add(
+                        egui::DragValue::new(&mut self.gif_options.max_height)
+                            .clamp_range(64..=render::MAX_IMAGE_SIZE),
+                    );
+                });
+                if !self.board_code_status.is_empty() {
+                    ui.label(&self.board_code_status);
+                }
+            });
+        if save_gif {
+            self.board_code_status = match self.save_gif() {
+                Ok(()) => format!("Saved the game to {}", self.gif_file),
+                Err(why) => format!("Save failed: {}", why),
+            };
+        }
+        if let Some(code) = play {
+            self.board_code_status = match self.start_shared_board(ctx, &code) {
+                Ok(()) => "Started the shared board".to_string(),
+                Err(why) => format!("Invalid board code: {}", why),
+            };
+        }
+        if open {
+            let path = std::path::PathBuf::from(&self.board_file);
+            let result = boardfile::load_board(&path).and_then(|board| {
+                let announcement = format!("Opened board {}", self.board_file);
+                self.start_board(ctx, board, announcement)
+            });
+            self.board_code_status = match result {
+                Ok(()) => format!("Opened the board from {}", self.board_file),
+                Err(why) => format!("Open failed: {}", why),
+            };
+        }
+    }
+
+    /// Animates the current game, as far as it's been played, in the current theme
+    fn save_gif(&self) -> Result<()> {
+        let replay = Replay::new(&self.gameboard, self.plays.replay_moves())
+            .filter(|r| !r.moves.is_empty())
+            .ok_or(anyhow::anyhow!("No moves have been made"))?;
+        let options = GifOptions {
+            render: RenderOptions {
+                palette: self.palette.clone(),
+                ..Default::default()
+            },
+            ..self.gif_options.clone()
+        };
+        replaygif::save_gif(&replay, std::path::Path::new(&self.gif_file), &options)
+    }
+
+    /// Loads the board textures from the selected skin. Any tiles missing from the
+    /// skin, or the whole skin if it can't be loaded, use the built-in images.
+    fn load_skin(&mut self, ctx: &egui::Context) {
+        let skin = self
+            .state
+            .skin
+            .as_ref()
+            .and_then(|name| match Skin::load(name) {
+                Ok(s) => {
+                    println!("Loaded {} tiles from skin '{}'", s.num_tiles(), name);
+                    Some(s)
+                }
+                Err(why) => {
+                    println!("Failed to load skin '{}': {}", name, why);
+                    None
+                }
+            });
+        self.textures = Some(TileTextures::load(ctx, skin.as_ref()));
+    }
+
+    /// Rebuilds the palette if the theme setting or the system's dark mode has
+    /// changed since it was last built. Custom themes are only loaded at startup.
+    fn update_palette(&mut self, system_dark: Option<bool>) {
+        let source = (self.state.theme.clone(), system_dark);
+        if self.palette_source.as_ref() != Some(&source) {
+            self.palette = Palette::for_theme(&source.0, system_dark, &self.custom_themes);
+            self.palette_source = Some(source);
+        }
+    }
+
+    /// Copies the question mark options onto the game board
+    fn apply_marking_rules(&mut self) {
+        self.gameboard.use_question_marks = self.state.question_marks;
+        self.gameboard.question_blocks_reveal = self.state.question_blocks_reveal;
+    }
+
     fn reset_existing_game(&mut self, _ctx: &egui::Context) -> Result<(), Error> {
+        self.record_abandoned_game();
+        // Replaying a board that's already been seen counts as an assist
+        self.board_replayed |= self.gameboard.is_populated;
         self.gameboard.reset_existing();
+        self.animations.skip();
 
         self.plays.clear();
         self.game_state = GameState::NotStarted;
-        self.game_started = now();
+        self.timer.reset();
 
         Ok(())
     }
@@ -241,9 +680,19 @@ impl MinesOfRustApp {
                 .populate_mines_around(self.game_settings.num_mines, Some(first_click))?;
         }
 
-        self.game_started = now();
+        self.timer.start();
+        self.started_at = Local::now().fixed_offset();
         self.game_state = GameState::Playing;
 
+        if let Some(date) = self.daily {
+            // Starting the ranked attempt uses it up, even if the game is abandoned
+            self.daily_ranked = !self.leaderboards.daily_attempted(&date);
+            if self.daily_ranked {
+                self.leaderboards.mark_daily_attempted(&date);
+                self.save_leaderboards();
+            }
+        }
+
         if self.game_settings.use_numerals {
             self.gameboard.populate_numerals()?;
         }
@@ -252,60 +701,252 @@ impl MinesOfRustApp {
     }
 
     fn leaderboard_ui(&mut self, ctx: &egui::Context) {
+        let mut imported = false;
         egui::Window::new("Leaderboard")
             .open(&mut self.leaderboard_visible)
             .vscroll(true)
             .hscroll(true)
             .show(ctx, |ui| {
-                egui::CollapsingHeader::new("Beginner")
-                    .default_open(self.state.difficulty == GameDifficulty::Beginner)
-                    .show(ui, |ui| {
-                        egui::Grid::new("leaderboard")
-                            .num_columns(2)
-                            .spacing([50.0, 5.0])
-                            .striped(true)
-                            .show(ui, |ui| {
-                                self.leaderboards.beginner.entries.iter().for_each(|e| {
-                                    ui.label(&e.player_name);
-                                    ui.label(format!("{:.2}", e.time));
-                                    ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
-                                    ui.end_row();
-                                });
-                            });
+                ui.horizontal(|ui| {
+                    ui.label("File (.json or .csv):");
+                    ui.text_edit_singleline(&mut self.leaderboard_file);
+                    let path = std::path::Path::new(&self.leaderboard_file);
+                    if ui.button("Export").clicked() {
+                        self.leaderboard_status = match self.leaderboards.export_to_file(path) {
+                            Ok(_) => format!("Exported to {}", self.leaderboard_file),
+                            Err(why) => format!("Export failed: {}", why),
+                        };
+                    }
+                    if ui.button("Import").clicked() {
+                        self.leaderboard_status = match LeaderBoards::import_from_file(path) {
+                            Ok(l) => {
+                                imported = true;
+                                format!("Imported {} new entries", self.leaderboards.merge(&l))
+                            }
+                            Err(why) => format!("Import failed: {}", why),
+                        };
+                    }
+                });
+                if !self.leaderboard_status.is_empty() {
+                    ui.label(&self.leaderboard_status);
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Rank by:");
+                    Ranking::all().into_iter().for_each(|r| {
+                        ui.selectable_value(&mut self.leaderboard_ranking, r, r.as_str());
                     });
+                    ui.checkbox(&mut self.leaderboard_no_flag, "No flags (NF)");
+                    ui.checkbox(&mut self.leaderboard_verified_only, "Verified only");
+                });
 
-                egui::CollapsingHeader::new("Intermediate")
-                    .default_open(self.state.difficulty == GameDifficulty::Intermediate)
-                    .show(ui, |ui| {
-                        egui::Grid::new("leaderboard")
-                            .num_columns(3)
-                            .spacing([50.0, 5.0])
-                            .striped(true)
+                let levels = [
+                    GameDifficulty::Beginner,
+                    GameDifficulty::Intermediate,
+                    GameDifficulty::Expert,
+                ];
+                levels.iter().for_each(|level| {
+                    egui::CollapsingHeader::new(level.as_str())
+                        .default_open(
+                            self.state.difficulty == *level && self.state.variant().is_classic(),
+                        )
+                        .show(ui, |ui| {
+                            MinesOfRustApp::leaderboard_table_ui(
+                                ui,
+                                &self.leaderboards.leaderboard_for(Variant::default(), level),
+                                self.leaderboard_ranking,
+                                self.leaderboard_no_flag,
+                                self.leaderboard_verified_only,
+                            );
+                        });
+                });
+
+                // Other kinds of board are ranked apart from the classic game
+                Variant::all()
+                    .into_iter()
+                    .filter(|v| !v.is_classic())
+                    .for_each(|v| {
+                        egui::CollapsingHeader::new(v.as_str())
+                            .default_open(self.state.variant() == v)
                             .show(ui, |ui| {
-                                self.leaderboards.intermediate.entries.iter().for_each(|e| {
-                                    ui.label(&e.player_name);
-                                    ui.label(format!("{:.2}", e.time));
-                                    ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
-                                    ui.end_row();
+                                levels.iter().for_each(|level| {
+                                    egui::CollapsingHeader::new(level.as_str())
+                                        .id_source((v.key(), level.as_str()))
+                                        .default_open(self.state.difficulty == *level)
+                                        .show(ui, |ui| {
+                                            MinesOfRustApp::leaderboard_table_ui(
+                                                ui,
+                                                &self.leaderboards.leaderboard_for(v, level),
+                                                self.leaderboard_ranking,
+                                                self.leaderboard_no_flag,
+                                                self.leaderboard_verified_only,
+                                            );
+                                        });
                                 });
                             });
                     });
 
-                egui::CollapsingHeader::new("Expert")
-                    .default_open(self.state.difficulty == GameDifficulty::Expert)
+                let today = daily::date_key(&daily::today());
+                egui::CollapsingHeader::new("Daily")
+                    .default_open(self.daily.is_some())
                     .show(ui, |ui| {
-                        egui::Grid::new("leaderboard")
-                            .num_columns(2)
-                            .spacing([50.0, 5.0])
-                            .striped(true)
-                            .show(ui, |ui| {
-                                self.leaderboards.expert.entries.iter().for_each(|e| {
-                                    ui.label(&e.player_name);
-                                    ui.label(format!("{:.2}", e.time));
-                                    ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
-                                    ui.end_row();
+                        if self.leaderboards.daily.is_empty() {
+                            ui.label("No daily boards played yet");
+                        }
+                        // Most recent day first
+                        self.leaderboards.daily.iter().rev().for_each(|(key, d)| {
+                            egui::CollapsingHeader::new(key)
+                                .default_open(*key == today)
+                                .show(ui, |ui| {
+                                    MinesOfRustApp::leaderboard_table_ui(
+                                        ui,
+                                        &d.leaderboard,
+                                        self.leaderboard_ranking,
+                                        self.leaderboard_no_flag,
+                                        self.leaderboard_verified_only,
+                                    );
                                 });
+                        });
+                    });
+            });
+        if imported {
+            self.save_leaderboards();
+        }
+    }
+
+    fn leaderboard_table_ui(
+        ui: &mut egui::Ui,
+        board: &LeaderBoard,
+        ranking: Ranking,
+        no_flag: bool,
+        verified_only: bool,
+    ) {
+        let stat = |v: Option<f64>| v.map(|v| format!("{:.3}", v)).unwrap_or_default();
+        egui::Grid::new("leaderboard")
+            .num_columns(7)
+            .spacing([30.0, 5.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ["Player", "Time", "3BV/s", "IOE", "Rules", "Date", "Replay"]
+                    .iter()
+                    .for_each(|h| {
+                        ui.strong(*h);
+                    });
+                ui.end_row();
+
+                board
+                    .ranked(ranking, no_flag, verified_only)
+                    .iter()
+                    .for_each(|e| {
+                        ui.label(&e.player_name);
+                        ui.label(format_time(e.time));
+                        ui.label(stat(e.three_bv_per_second()));
+                        ui.label(stat(e.ioe()));
+                        ui.label(e.rules_label());
+                        ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
+                        match &e.verification {
+                            Verification::Verified => {
+                                ui.label("✔ Verified");
+                            }
+                            Verification::Failed(why) => {
+                                ui.colored_label(ui.visuals().warn_fg_color, "⚠ Unverified")
+                                    .on_hover_text(why);
+                            }
+                            Verification::Unchecked => {
+                                ui.label("");
+                            }
+                        }
+                        ui.end_row();
+                    });
+            });
+    }
+
+    fn history_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new("History")
+            .open(&mut self.history_visible)
+            .vscroll(true)
+            .hscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let q = &mut self.history_query;
+                    egui::ComboBox::new("HistoryDifficulty", "")
+                        .selected_text(q.difficulty.as_ref().map(|d| d.as_str()).unwrap_or("All"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut q.difficulty, None, "All");
+                            [
+                                GameDifficulty::Beginner,
+                                GameDifficulty::Intermediate,
+                                GameDifficulty::Expert,
+                            ]
+                            .into_iter()
+                            .for_each(|d| {
+                                let label = d.as_str();
+                                ui.selectable_value(&mut q.difficulty, Some(d), label);
                             });
+                        });
+                    egui::ComboBox::new("HistoryOutcome", "")
+                        .selected_text(q.outcome.as_ref().map(|o| o.as_str()).unwrap_or("All"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut q.outcome, None, "All");
+                            [Outcome::Won, Outcome::Lost, Outcome::Abandoned]
+                                .into_iter()
+                                .for_each(|o| {
+                                    let label = o.as_str();
+                                    ui.selectable_value(&mut q.outcome, Some(o), label);
+                                });
+                        });
+                });
+
+                let records = self.history.query(&self.history_query);
+                let summary = HistorySummary::from_records(&records);
+                ui.label(format!(
+                    "{} games: {} won, {} lost, {} abandoned",
+                    summary.games, summary.wins, summary.losses, summary.abandoned
+                ));
+                if let (Some(best), Some(average)) = (summary.best_time, summary.average_win_time) {
+                    ui.label(format!(
+                        "Best time: {}, average winning time: {}",
+                        format_time(best),
+                        format_time(average)
+                    ));
+                }
+                ui.separator();
+
+                egui::Grid::new("history")
+                    .num_columns(7)
+                    .spacing([20.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        [
+                            "Date",
+                            "Difficulty",
+                            "Outcome",
+                            "Time",
+                            "3BV",
+                            "Clicks",
+                            "Cleared",
+                        ]
+                        .iter()
+                        .for_each(|h| {
+                            ui.strong(*h);
+                        });
+                        ui.end_row();
+
+                        records.iter().for_each(|r| {
+                            ui.label(format!("{}", r.started.format("%Y-%m-%d %H:%M")));
+                            ui.label(r.ruleset.difficulty.as_str());
+                            if r.assists_used {
+                                ui.label(format!("{} (replay)", r.outcome.as_str()));
+                            } else {
+                                ui.label(r.outcome.as_str());
+                            }
+                            ui.label(format_time(r.duration));
+                            ui.label(format!("{}", r.three_bv));
+                            ui.label(format!("{}", r.clicks.total));
+                            ui.label(format!("{:.0}%", r.percent_cleared));
+                            ui.end_row();
+                        });
                     });
             });
     }
@@ -362,9 +1003,10 @@ impl MinesOfRustApp {
             });
     }
 
-    fn on_update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Result<(), Error> {
+    fn on_update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) -> Result<(), Error> {
         if !self.image_loaders_installed {
             install_image_loaders(ctx);
+            self.load_skin(ctx);
             self.image_loaders_installed = true;
         }
 
@@ -376,9 +1018,26 @@ impl MinesOfRustApp {
             self.gamestats_ui(ctx);
         }
 
-        match self.state.theme {
-            VisualTheme::Dark => ctx.set_visuals(Visuals::dark()),
-            VisualTheme::Light => ctx.set_visuals(Visuals::light()),
+        if self.options_visible {
+            self.more_options_ui(ctx);
+        }
+
+        if self.history_visible {
+            self.history_ui(ctx);
+        }
+
+        if self.board_code_visible {
+            self.board_code_ui(ctx);
+        }
+
+        self.save_error_ui(ctx);
+
+        let system_dark = frame.info().system_theme.map(|t| t == Theme::Dark);
+        self.update_palette(system_dark);
+        if self.palette.dark {
+            ctx.set_visuals(Visuals::dark());
+        } else {
+            ctx.set_visuals(Visuals::light());
         }
 
         if DBG_WINDOW_RESIZABLE {
@@ -411,8 +1070,15 @@ impl MinesOfRustApp {
                 if ui.input_mut(|i| {
                     i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Q))
                 }) {
-                    println!("Boss can see screen. Ctrl+q is pressed, exiting");
-                    process::exit(0);
+                    println!("Ctrl+q is pressed, exiting");
+                    // Closing the viewport lets eframe call on_exit, which saves our state
+                    ctx.send_viewport_cmd(ViewportCommand::Close);
+                }
+                if ui.input_mut(|i| {
+                    i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::B))
+                }) {
+                    println!("Boss can see screen. Ctrl+b is pressed, hiding");
+                    self.boss_key(ctx);
                 }
                 if ui.input_mut(|i| {
                     i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::P))
@@ -420,6 +1086,26 @@ impl MinesOfRustApp {
                     println!("Ctrl+q is pressed, toggling pause status");
                     self.toggle_pause_state();
                 }
+                if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    self.animations.skip();
+                }
+                // Pasting a board code anywhere but a text field starts that board
+                if !ctx.wants_keyboard_input() {
+                    let pasted = ui.input(|i| {
+                        i.events.iter().find_map(|e| match e {
+                            egui::Event::Paste(s) if BoardCode::from_code(s).is_ok() => {
+                                Some(s.clone())
+                            }
+                            _ => None,
+                        })
+                    });
+                    if let Some(code) = pasted {
+                        println!("Board code pasted, starting shared board");
+                        if let Err(why) = self.start_shared_board(ctx, &code) {
+                            self.announcement = format!("Invalid board code: {}", why);
+                        }
+                    }
+                }
 
                 ui.vertical_centered(|ui| {
                     let resp = self.face_ui(ui);
@@ -467,10 +1153,25 @@ impl MinesOfRustApp {
                         if ui.button("Game Stats").clicked() {
                             self.gamestats_visible = true;
                         }
+                        if ui.button("History").clicked() {
+                            self.history_visible = true;
+                        }
+                        if ui.button("Board Code").clicked() {
+                            self.board_code_status.clear();
+                            self.board_code_visible = true;
+                        }
+                        if ui.button("Daily").clicked() {
+                            self.reset_daily_game(ctx)
+                                .expect("Error building daily game");
+                        }
+                        if ui.button("Options").clicked() {
+                            self.available_skins = Skin::available();
+                            self.options_visible = true;
+                        }
                     });
                 });
             });
-        if self.game_state == GameState::Playing {
+        if self.timer.is_running() || self.animations.is_running(ctx.input(|i| i.time)) {
             ctx.request_repaint();
         }
         Ok(())
@@ -490,39 +1191,90 @@ impl MinesOfRustApp {
                 && self.gameboard.is_loss_configuration()
             {
                 self.game_state = GameState::EndedLoss;
-                self.game_finished = now();
+                self.timer.stop();
+                self.record_game(Outcome::Lost);
                 self.losses += 1;
+                self.announcement = match &self.detonated_on {
+                    Some(c) => format!(
+                        "Mine detonated at {}. Game lost",
+                        a11y::describe_coordinate(c)
+                    ),
+                    None => "Game lost".to_string(),
+                };
+                self.animate_loss(ui.input(|i| i.time));
                 "".to_string()
             } else if self.game_state == GameState::Playing && self.gameboard.is_win_configuration()
             {
                 // You win!
                 self.game_state = GameState::EndedWin;
                 self.gameboard.flag_all_mines();
-                self.game_finished = now();
+                let time = self.timer.stop().as_secs_f64();
+                self.record_game(Outcome::Won);
                 self.wins += 1;
-                self.leaderboards.add(
-                    self.state.difficulty.clone(),
-                    &whoami::realname(), // Do this until I write a dialog asking for the real name
-                    self.game_finished - self.game_started,
-                );
+                self.animations
+                    .celebrate(&self.state.animation_speed, ui.input(|i| i.time));
+                let entry = Entry {
+                    three_bv: Some(self.gameboard.three_bv()),
+                    clicks: Some(self.plays.clicks()),
+                    flags_used: Some(self.plays.flagged()),
+                    left_click_chord: Some(self.state.left_click_chord),
+                    fog_of_war: Some(self.state.fog_of_war),
+                    replay: Replay::new(&self.gameboard, self.plays.replay_moves())
+                        .map(|r| r.to_code()),
+                    // Do this until I write a dialog asking for the real name
+                    ..Entry::new(&whoami::realname(), time)
+                };
+                match self.daily {
+                    Some(date) if self.daily_ranked => {
+                        self.leaderboards.add_daily_entry(&date, entry);
+                        self.save_leaderboards();
+                    }
+                    // Daily practice games and shared boards aren't ranked
+                    Some(_) => {}
+                    None if self.shared_board => {}
+                    None => {
+                        self.leaderboards.add_entry_for(
+                            self.gameboard.variant,
+                            self.state.difficulty.clone(),
+                            entry,
+                        );
+                        self.save_leaderboards();
+                    }
+                }
+                self.announcement =
+                    format!("Board cleared in {} seconds. You win", format_time(time));
                 "".to_string()
-            } else if self.game_state == GameState::Playing {
-                format!("Time: {:.2}", now() - self.game_started)
-            } else if self.game_state == GameState::Paused {
-                format!("Time: {:.2}", self.game_started)
-            } else if self.game_state.game_ended() {
-                format!("Time: {:.2}", self.game_finished - self.game_started)
+            } else if self.game_state != GameState::NotStarted {
+                format!("Time: {}", format_time(self.timer.elapsed().as_secs_f64()))
             } else {
                 "".to_string()
             };
 
             ui.add(egui::Label::new(<String as Into<RichText>>::into(s).heading()).wrap(false));
 
+            if let Some(date) = &self.daily {
+                ui.label(format!(
+                    "Daily {} ({})",
+                    daily::date_key(date),
+                    if self.daily_ranked {
+                        "ranked"
+                    } else {
+                        "practice"
+                    }
+                ));
+            }
+
             if self.game_state == GameState::Playing && ui.button("Pause").clicked() {
                 self.pause_game();
             } else if self.game_state == GameState::Paused && ui.button("Resume").clicked() {
                 self.resume_game();
             }
+
+            // Game events are announced to screen readers through a live region
+            let resp = ui.small(&self.announcement);
+            ui.ctx().accesskit_node_builder(resp.id, |b| {
+                b.set_live(egui::accesskit::Live::Polite);
+            });
         });
     }
 
@@ -536,12 +1288,12 @@ impl MinesOfRustApp {
 
     fn pause_game(&mut self) {
         self.game_state = GameState::Paused;
-        self.game_started = now() - self.game_started;
+        self.timer.pause();
     }
 
     fn resume_game(&mut self) {
         self.game_state = GameState::Playing;
-        self.game_started = now() - self.game_started;
+        self.timer.resume();
     }
 
     fn options_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
@@ -580,6 +1332,44 @@ impl MinesOfRustApp {
                 });
                 ui.end_row();
 
+                ui.label("Board:");
+                let cb = egui::ComboBox::new("Neighborhood", "")
+                    .width(0_f32)
+                    .selected_text(self.state.neighborhood.as_str());
+                cb.show_ui(ui, |ui| {
+                    let mut changed = false;
+                    Neighborhood::all().into_iter().for_each(|n| {
+                        changed |= ui
+                            .selectable_value(&mut self.state.neighborhood, n, n.as_str())
+                            .changed();
+                    });
+                    if changed {
+                        self.reset_new_game(ctx).expect("Failed to reset game");
+                    }
+                });
+                ui.end_row();
+
+                // Boards that can't wrap are always bounded
+                ui.label("Edges:");
+                let can_wrap = self.state.neighborhood.can_wrap();
+                ui.add_enabled_ui(can_wrap, |ui| {
+                    let cb = egui::ComboBox::new("Topology", "")
+                        .width(0_f32)
+                        .selected_text(self.state.variant().topology.as_str());
+                    cb.show_ui(ui, |ui| {
+                        let mut changed = false;
+                        Topology::all().into_iter().for_each(|t| {
+                            changed |= ui
+                                .selectable_value(&mut self.state.topology, t, t.as_str())
+                                .changed();
+                        });
+                        if changed {
+                            self.reset_new_game(ctx).expect("Failed to reset game");
+                        }
+                    });
+                });
+                ui.end_row();
+
                 ui.label("Left Click Chords:");
                 toggle_ui(ui, &mut self.state.left_click_chord);
                 ui.end_row();
@@ -593,12 +1383,183 @@ impl MinesOfRustApp {
                     .width(0_f32)
                     .selected_text(self.state.theme.as_str());
                 cb.show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.state.theme, VisualTheme::Dark, "Dark");
-                    ui.selectable_value(&mut self.state.theme, VisualTheme::Light, "Light");
+                    [
+                        VisualTheme::Dark,
+                        VisualTheme::Light,
+                        VisualTheme::Deuteranopia,
+                        VisualTheme::Protanopia,
+                        VisualTheme::HighContrast,
+                        VisualTheme::System,
+                    ]
+                    .into_iter()
+                    .for_each(|t| {
+                        let label = t.as_str().to_string();
+                        ui.selectable_value(&mut self.state.theme, t, label);
+                    });
+                    self.custom_themes.iter().for_each(|p| {
+                        ui.selectable_value(
+                            &mut self.state.theme,
+                            VisualTheme::Custom(p.name.clone()),
+                            &p.name,
+                        );
+                    });
                 });
             });
     }
 
+    /// Less frequently used options, shown in their own window
+    fn more_options_ui(&mut self, ctx: &egui::Context) {
+        let mut visible = self.options_visible;
+        egui::Window::new("Options")
+            .open(&mut visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("more_options")
+                    .num_columns(2)
+                    .spacing([5.0, 5.0])
+                    .min_row_height(30.0)
+                    .striped(false)
+                    .show(ui, |ui| {
+                        ui.label("Question Marks:");
+                        let q = toggle_ui(ui, &mut self.state.question_marks);
+                        ui.end_row();
+
+                        ui.label("Question Marks Block Reveal:");
+                        let qb = toggle_ui(ui, &mut self.state.question_blocks_reveal);
+                        ui.end_row();
+
+                        if q.changed() || qb.changed() {
+                            self.apply_marking_rules();
+                        }
+
+                        ui.label("Input Scheme:");
+                        let cb = egui::ComboBox::new("InputScheme", "")
+                            .width(0_f32)
+                            .selected_text(self.state.input_scheme.as_str());
+                        cb.show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.state.input_scheme,
+                                InputScheme::Standard,
+                                InputScheme::Standard.as_str(),
+                            );
+                            ui.selectable_value(
+                                &mut self.state.input_scheme,
+                                InputScheme::Classic,
+                                InputScheme::Classic.as_str(),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Renderer:");
+                        let cb = egui::ComboBox::new("TileRenderer", "")
+                            .width(0_f32)
+                            .selected_text(self.state.renderer.as_str());
+                        cb.show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.state.renderer,
+                                TileRenderer::Images,
+                                TileRenderer::Images.as_str(),
+                            );
+                            ui.selectable_value(
+                                &mut self.state.renderer,
+                                TileRenderer::Vector,
+                                TileRenderer::Vector.as_str(),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Large Numerals (Vector):");
+                        toggle_ui(ui, &mut self.state.large_numerals);
+                        ui.end_row();
+
+                        ui.label("Animations:");
+                        let cb = egui::ComboBox::new("AnimationSpeed", "")
+                            .width(0_f32)
+                            .selected_text(self.state.animation_speed.as_str());
+                        cb.show_ui(ui, |ui| {
+                            [
+                                AnimationSpeed::Off,
+                                AnimationSpeed::Slow,
+                                AnimationSpeed::Normal,
+                                AnimationSpeed::Fast,
+                            ]
+                            .into_iter()
+                            .for_each(|a| {
+                                let label = a.as_str();
+                                ui.selectable_value(&mut self.state.animation_speed, a, label);
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Skin:");
+                        let mut skin_changed = false;
+                        let cb = egui::ComboBox::new("Skin", "")
+                            .width(0_f32)
+                            .selected_text(self.state.skin.as_deref().unwrap_or("Built-in"));
+                        cb.show_ui(ui, |ui| {
+                            skin_changed |= ui
+                                .selectable_value(&mut self.state.skin, None, "Built-in")
+                                .changed();
+                            self.available_skins.iter().for_each(|name| {
+                                skin_changed |= ui
+                                    .selectable_value(
+                                        &mut self.state.skin,
+                                        Some(name.clone()),
+                                        name,
+                                    )
+                                    .changed();
+                            });
+                        });
+                        if skin_changed {
+                            self.load_skin(ctx);
+                        }
+                        ui.end_row();
+                    });
+            });
+        if self.options_visible && !visible {
+            self.save_settings();
+        }
+        self.options_visible = visible;
+    }
+
+    /// Ripples in the squares uncovered by a play at `origin`. `was_revealed` is the
+    /// revealed state of each square before the play.
+    fn animate_reveals(&mut self, origin: &Coordinate, was_revealed: &[bool], time: f64) {
+        let revealed = iproduct!(0..self.gameboard.height, 0..self.gameboard.width)
+            .zip(self.gameboard.squares.iter().zip(was_revealed))
+            .filter(|(_, (s, was))| s.is_revealed && !**was)
+            .map(|((y, x), _)| Coordinate { x, y })
+            .collect::<Vec<Coordinate>>();
+        if !revealed.is_empty() {
+            self.animations
+                .ripple(&self.state.animation_speed, origin, &revealed, time, |c| {
+                    self.gameboard.neighbors(c)
+                });
+        }
+    }
+
+    /// Shows the mines and incorrect flags one at a time after a loss
+    fn animate_loss(&mut self, time: f64) {
+        let squares = iproduct!(0..self.gameboard.height, 0..self.gameboard.width)
+            .zip(self.gameboard.squares.iter())
+            .filter(|((y, x), s)| {
+                let detonated = self
+                    .detonated_on
+                    .as_ref()
+                    .map(|c| c.matches(*x, *y))
+                    .unwrap_or(false);
+                (s.is_mine() || s.is_flagged()) && !detonated
+            })
+            .map(|((y, x), _)| Coordinate { x, y })
+            .collect::<Vec<Coordinate>>();
+        self.animations.mine_sequence(
+            &self.state.animation_speed,
+            self.detonated_on.as_ref(),
+            &squares,
+            time,
+        );
+    }
+
     /// Returns the first found Explosion in a list of cascaded play results
     fn first_losing_square_of_vec(play_result: &[PlayResult]) -> Option<Coordinate> {
         for r in play_result {
@@ -619,17 +1580,28 @@ impl MinesOfRustApp {
     }
 
     fn game_board_paused_ui(&mut self, ui: &mut egui::Ui) {
-        let desired_size = ui.spacing().interact_size.x
-            * egui::vec2(
-                self.game_settings.width as f32,
-                self.game_settings.height as f32,
-            );
+        let desired_size = match self.gameboard.variant.neighborhood {
+            Neighborhood::Square | Neighborhood::Knight | Neighborhood::Orthogonal => {
+                ui.spacing().interact_size.x
+                    * egui::vec2(
+                        self.game_settings.width as f32,
+                        self.game_settings.height as f32,
+                    )
+            }
+            Neighborhood::Hex => HexGrid {
+                origin: Pos2::ZERO,
+                size: ui.spacing().interact_size.x,
+                width: self.gameboard.width,
+                height: self.gameboard.height,
+            }
+            .board_size(),
+        };
         let (rect, _) = ui.allocate_exact_size(desired_size, egui::Sense::click());
         ui.painter().rect(
             rect,
             1.0,
-            constants::COLOR_REVEALED,
-            Stroke::new(1.0, constants::COLOR_BORDER),
+            self.palette.revealed,
+            Stroke::new(1.0, self.palette.border),
         );
     }
 
@@ -647,7 +1619,54 @@ impl MinesOfRustApp {
             Coordinate { x: 9999, y: 9999 }
         };
 
-        egui::Grid::new("process_grid_outputs")
+        // Classic simultaneous left+right chording
+        let chord_frame = if self.state.input_scheme == InputScheme::Classic {
+            let (primary_down, secondary_down) =
+                ui.input(|i| (i.pointer.primary_down(), i.pointer.secondary_down()));
+            self.button_chord.update(primary_down, secondary_down)
+        } else {
+            self.button_chord = ButtonChord::Idle;
+            ButtonChordFrame::default()
+        };
+
+        // Squares drawn pressed while a button is held down over the board
+        let (primary_down, secondary_down, middle_down) = ui.input(|i| {
+            (
+                i.pointer.primary_down(),
+                i.pointer.secondary_down(),
+                i.pointer.button_down(egui::PointerButton::Middle),
+            )
+        });
+        let press_preview = match self.hovered_square.clone() {
+            Some(c) if active => {
+                let is_revealed = self
+                    .gameboard
+                    .get_square_by_coordinate(&c)
+                    .map(|s| s.is_revealed)
+                    .unwrap_or(false);
+                if self.button_chord.is_pressed() || middle_down {
+                    Some(PressPreview::Area(c))
+                } else if primary_down && !secondary_down && self.button_chord == ButtonChord::Idle
+                {
+                    if self.state.left_click_chord && is_revealed {
+                        Some(PressPreview::Area(c))
+                    } else {
+                        Some(PressPreview::Square(c))
+                    }
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        self.hovered_square = None;
+
+        if self.gameboard.variant.neighborhood == Neighborhood::Hex {
+            self.hex_board_ui(ui, active, &chord_frame, press_preview.as_ref());
+            return;
+        }
+
+        let board_rect = egui::Grid::new("process_grid_outputs")
             .spacing([0.0, 0.0])
             .striped(false)
             .show(ui, |ui| {
@@ -663,96 +1682,336 @@ impl MinesOfRustApp {
                         false
                     };
 
+                    let pressed = if let Some(p) = &press_preview {
+                        p.covers(&Coordinate { x, y }, &self.gameboard)
+                    } else {
+                        false
+                    };
+
                     let resp = self.square_ui(
                         ui,
                         &sqr,
+                        &Coordinate { x, y },
                         detonated,
-                        mouse_over_coord.distance(&Coordinate { x, y }),
+                        pressed,
+                        self.gameboard
+                            .distance(&mouse_over_coord, &Coordinate { x, y }),
                     );
-                    if resp.clicked() && self.game_state == GameState::NotStarted {
-                        self.start_game(Coordinate { x, y })
-                            .expect("Error starting game");
-                    }
-
-                    let play_type = if active
-                        && resp.clicked_by(egui::PointerButton::Primary)
-                        && !self.state.left_click_chord
-                    {
-                        Some(RevealType::Reveal)
-                    } else if active
-                        && resp.clicked_by(egui::PointerButton::Primary)
-                        && self.state.left_click_chord
-                    {
-                        Some(RevealType::RevealChord)
-                    } else if active && resp.clicked_by(egui::PointerButton::Middle) {
-                        Some(RevealType::Chord)
-                    } else if resp.clicked_by(egui::PointerButton::Secondary) && active {
-                        Some(RevealType::Flag)
-                    } else {
-                        None
-                    };
-
-                    if let Some(p) = play_type {
-                        self.plays.push(PlayEntry {
-                            play_type: p.clone(),
-                            coord: Coordinate { x, y },
-                        });
-
-                        if let Some(c) = MinesOfRustApp::first_losing_square(
-                            &self
-                                .gameboard
-                                .play(x, y, p)
-                                .expect("Failed to play desired move"),
-                        ) {
-                            println!("Detonated on {:?}", c);
-                            self.detonated_on = Some(c.clone());
-                        }
+                    if resp.contains_pointer() {
+                        self.hovered_square = Some(Coordinate { x, y });
                     }
+                    self.square_input(ui, &resp, Coordinate { x, y }, active, &chord_frame);
 
                     if x == self.gameboard.width - 1 {
                         ui.end_row();
                     }
                 });
+            })
+            .response
+            .rect;
+
+        // The edges of a board that wraps around are marked where they carry on
+        wrap_marks(&self.gameboard, ui.spacing().interact_size.x)
+            .into_iter()
+            .for_each(|r| {
+                ui.painter().rect_filled(
+                    r.translate(board_rect.min.to_vec2()),
+                    0.0,
+                    self.palette.flag,
+                );
             });
     }
 
+    /// The board of a hexagonal game. Each hexagon is a widget covering the
+    /// rectangle between its flat sides, so it can be focused and labelled, while
+    /// the board beneath takes clicks on the points of the hexagons, which would
+    /// otherwise overlap.
+    fn hex_board_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        active: bool,
+        chord_frame: &ButtonChordFrame,
+        press_preview: Option<&PressPreview>,
+    ) {
+        let mut grid = HexGrid {
+            origin: Pos2::ZERO,
+            size: ui.spacing().interact_size.x,
+            width: self.gameboard.width,
+            height: self.gameboard.height,
+        };
+        // Only the hexagons are focused, not the board around them
+        let sense = egui::Sense {
+            focusable: false,
+            ..egui::Sense::click()
+        };
+        let (rect, resp) = ui.allocate_exact_size(grid.board_size(), sense);
+        resp.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Other, "Hexagonal board"));
+        grid.origin = rect.min;
+        // The hexagons' widgets are on top of the board, so it isn't hovered itself
+        let pointer = resp
+            .contains_pointer()
+            .then(|| ui.input(|i| i.pointer.hover_pos()))
+            .flatten();
+        let hovered = pointer.and_then(|p| grid.coordinate_at(p));
+        self.hovered_square = hovered.clone();
+
+        iproduct!(0..self.gameboard.height, 0..self.gameboard.width).for_each(|(y, x)| {
+            let coord = Coordinate { x, y };
+            let sqr = self
+                .gameboard
+                .get_square(x, y)
+                .expect("Error retrieving square");
+            let detonated = self.detonated_on.as_ref() == Some(&coord);
+            let pressed = press_preview
+                .map(|p| p.covers(&coord, &self.gameboard))
+                .unwrap_or(false);
+            let mouse_distance = hovered
+                .as_ref()
+                .map(|h| grid.distance(h, &coord))
+                .unwrap_or(f32::INFINITY);
+            let tile = self.tile_for(ui, &sqr, &coord, detonated, pressed);
+            self.paint_square(
+                ui,
+                grid.tile_rect(&coord),
+                Some(&grid.corners(&coord)),
+                &coord,
+                tile,
+                mouse_distance,
+            );
+
+            let hex_resp = ui.interact(
+                grid.input_rect(&coord),
+                ui.id().with(("hexagon", x, y)),
+                egui::Sense::click(),
+            );
+            let hidden = mouse_distance > 1.5
+                && self.state.fog_of_war
+                && self.game_state == GameState::Playing;
+            hex_resp.widget_info(|| {
+                egui::WidgetInfo::labeled(
+                    egui::WidgetType::Button,
+                    a11y::describe_square(&coord, &tile, hidden),
+                )
+            });
+            self.square_input(ui, &hex_resp, coord, active, chord_frame);
+        });
+
+        // The points of the hexagon under the pointer are outside its widget
+        if let (Some(coord), Some(p)) = (hovered, pointer) {
+            if !grid.input_rect(&coord).contains(p) {
+                self.square_input(ui, &resp, coord, active, chord_frame);
+            }
+        }
+    }
+
+    /// Starts the game, or makes a play, on a square clicked or given a key press.
+    /// `resp` is the response of the widget the square is drawn in.
+    fn square_input(
+        &mut self,
+        ui: &egui::Ui,
+        resp: &egui::Response,
+        coord: Coordinate,
+        active: bool,
+        chord_frame: &ButtonChordFrame,
+    ) {
+        let (x, y) = (coord.x, coord.y);
+        if resp.clicked()
+            && !chord_frame.suppress_clicks
+            && self.game_state == GameState::NotStarted
+        {
+            // The daily board's mines are placed before the first click, with only
+            // the centre square sure to be safe, so the game can only start there.
+            // Otherwise a first click could lose the day's ranked attempt.
+            let start = daily::safe_start(&self.game_settings);
+            if self.daily.is_some() && coord != start {
+                self.announcement = format!(
+                    "The daily board starts at the centre square, {}",
+                    a11y::describe_coordinate(&start)
+                );
+                return;
+            }
+
+            // A board that can't be populated, such as a shared one with too many
+            // mines to keep the first click clear, is left unstarted
+            if let Err(why) = self.start_game(Coordinate { x, y }) {
+                self.announcement = format!("Unable to start the game: {:?}", why);
+                return;
+            }
+        }
+
+        let play_type = if chord_frame.suppress_clicks {
+            if active
+                && chord_frame.execute
+                && resp.contains_pointer()
+                && self.game_state == GameState::Playing
+            {
+                Some(RevealType::Chord)
+            } else {
+                None
+            }
+        } else if active && resp.clicked() && !self.state.left_click_chord {
+            // A primary click, or enter or space on the focused square
+            Some(RevealType::Reveal)
+        } else if active && resp.clicked() && self.state.left_click_chord {
+            Some(RevealType::RevealChord)
+        } else if active && resp.clicked_by(egui::PointerButton::Middle) {
+            Some(RevealType::Chord)
+        } else if resp.clicked_by(egui::PointerButton::Secondary) && active {
+            Some(RevealType::Flag)
+        } else if active && resp.has_focus() && ui.input(|i| i.key_pressed(Key::F)) {
+            // Keyboard flagging of the focused square
+            Some(RevealType::Flag)
+        } else if active
+            && resp.has_focus()
+            && self.game_state == GameState::Playing
+            && ui.input(|i| i.key_pressed(Key::C))
+        {
+            // Keyboard chording of the focused square
+            Some(RevealType::Chord)
+        } else {
+            None
+        };
+
+        if let Some(p) = play_type {
+            self.plays.push(PlayEntry {
+                play_type: p.clone(),
+                coord: Coordinate { x, y },
+                time: self.timer.elapsed().as_secs_f64(),
+            });
+
+            let num_revealed = self.gameboard.num_revealed();
+            let was_revealed = self
+                .gameboard
+                .squares
+                .iter()
+                .map(|s| s.is_revealed)
+                .collect::<Vec<bool>>();
+            let result = self
+                .gameboard
+                .play(x, y, p)
+                .expect("Failed to play desired move");
+            self.animate_reveals(&Coordinate { x, y }, &was_revealed, ui.input(|i| i.time));
+            self.announcement = a11y::describe_play(
+                &Coordinate { x, y },
+                &result,
+                self.gameboard.num_revealed() - num_revealed,
+            );
+
+            if let Some(c) = MinesOfRustApp::first_losing_square(&result) {
+                println!("Detonated on {:?}", c);
+                self.detonated_on = Some(c.clone());
+            }
+        }
+    }
+
     fn face_ui(&self, ui: &mut egui::Ui) -> egui::Response {
         let desired_size = ui.spacing().interact_size.x * egui::vec2(1.4, 1.4);
         let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
 
-        if self.game_state == GameState::EndedLoss {
-            egui::Image::new(egui::include_image!("../assets/loss.png")).paint_at(ui, rect);
+        let face = if self.game_state == GameState::EndedLoss {
+            SkinTile::FaceLoss
         } else if self.game_state == GameState::EndedWin {
-            egui::Image::new(egui::include_image!("../assets/win.png")).paint_at(ui, rect);
+            SkinTile::FaceWin
         } else {
-            egui::Image::new(egui::include_image!("../assets/happy.png")).paint_at(ui, rect);
-        }
+            SkinTile::FaceHappy
+        };
+        self.paint_tile(ui, rect, face, Color32::WHITE);
 
         response
     }
 
+    /// Paints a tile with the selected renderer. The vector renderer draws the board
+    /// tiles itself and defers to the images for the faces.
+    fn paint_tile(&self, ui: &egui::Ui, rect: egui::Rect, tile: SkinTile, tint: Color32) {
+        let is_face = matches!(
+            tile,
+            SkinTile::FaceHappy | SkinTile::FaceWin | SkinTile::FaceLoss
+        );
+
+        if self.state.renderer == TileRenderer::Vector && !is_face {
+            let painter = ui.painter();
+            match tile {
+                SkinTile::Numeral(n) => {
+                    vector::paint_numeral(painter, rect, n, tint, self.state.large_numerals)
+                }
+                SkinTile::Flag => vector::paint_flag(painter, rect, tint),
+                SkinTile::Mine => vector::paint_mine(painter, rect),
+                SkinTile::MisFlag => vector::paint_cross(painter, rect),
+                SkinTile::FlagCorrect => vector::paint_check(painter, rect),
+                SkinTile::Question => vector::paint_question(painter, rect),
+                SkinTile::Unrevealed => vector::paint_bevel(painter, rect),
+                _ => {}
+            }
+        } else if let Some(t) = &self.textures {
+            t.paint(ui, rect, tile, tint);
+        }
+    }
+
     fn square_ui(
         &self,
         ui: &mut egui::Ui,
         sqr: &Square,
+        coord: &Coordinate,
         is_detonated: bool,
+        is_pressed: bool,
         mouse_distance: f32,
     ) -> egui::Response {
-        let opaque = mouse_distance > 1.5 && self.state.fog_of_war;
-
         let desired_size = (ui.spacing().interact_size.x) * egui::vec2(1.0, 1.0);
         let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
-        let visuals_off = ui.style().interact_selectable(&response, false);
-        let visuals_on = ui.style().interact_selectable(&response, true);
 
-        let unrevealed_color = visuals_on.bg_fill; //constants::COLOR_UNREVEALED;
-        let revealed_color = if is_detonated {
-            constants::COLOR_DETONATED
+        let tile = self.tile_for(ui, sqr, coord, is_detonated, is_pressed);
+        let hidden =
+            mouse_distance > 1.5 && self.state.fog_of_war && self.game_state == GameState::Playing;
+        response.widget_info(|| {
+            egui::WidgetInfo::labeled(
+                egui::WidgetType::Button,
+                a11y::describe_square(coord, &tile, hidden),
+            )
+        });
+
+        self.paint_square(ui, rect, None, coord, tile, mouse_distance);
+        response
+    }
+
+    /// How a square is shown. Squares still waiting on an animation are drawn as
+    /// they were mid-game.
+    fn tile_for(
+        &self,
+        ui: &egui::Ui,
+        sqr: &Square,
+        coord: &Coordinate,
+        is_detonated: bool,
+        is_pressed: bool,
+    ) -> Tile {
+        if self
+            .animations
+            .is_pending(coord.x, coord.y, ui.input(|i| i.time))
+        {
+            let mut before = *sqr;
+            before.is_revealed = false;
+            Tile::for_square(&before, &GameState::Playing, false, is_pressed)
         } else {
-            visuals_off.bg_fill
-        };
-        let border_color = constants::COLOR_BORDER;
-        let misflagged_color = constants::COLOR_MISFLAGGED;
+            Tile::for_square(sqr, &self.game_state, is_detonated, is_pressed)
+        }
+    }
+
+    /// Draws a square's tile in `rect`. A hexagon is filled and outlined along its
+    /// corners, with the tile drawn in the square inside it.
+    fn paint_square(
+        &self,
+        ui: &egui::Ui,
+        rect: egui::Rect,
+        hexagon: Option<&[Pos2]>,
+        coord: &Coordinate,
+        tile: Tile,
+        mouse_distance: f32,
+    ) {
+        let opaque = mouse_distance > 1.5 && self.state.fog_of_war;
+
+        let unrevealed_color = self.palette.unrevealed;
+        let revealed_color = self.palette.revealed;
+        let border_color = self.palette.border;
+        let misflagged_color = self.palette.misflagged;
 
         let opaque_color = Color32::from_rgba_unmultiplied(
             unrevealed_color.r(),
@@ -766,62 +2025,93 @@ impl MinesOfRustApp {
                 255
             },
         );
+        let t = ui.input(|i| i.time);
 
-        ui.painter()
-            .rect(rect, 0.0, revealed_color, Stroke::new(0.5, border_color));
-
-        // Note: These are insufficient.
-        // Playing
-        //      Unrevealed
-        //      Unrevealed Flagged
-        //      Revealed numeral
-        //      Revealed blank
-        //      Unrevealed, Mouse down, left button
-        //      Unrevealed, Mouse down, chord
-        // Loss
-        //      Unrevealed
-        //      Unrevealed non-mined flagged
-        //      Unrevealed mined flagged
-        //      Revealed mined (losing play)
-        //      Revealed mined (adjacent to losing play)
-        //      Revealed numeral
-        //      Revealed blank
-        // Win
-        //      Unrevealed
-        //      Unrevealed flagged
-        //      Revealed numeral
-        //      Revealed blank
-        if sqr.is_mine() && !sqr.is_flagged && self.game_state == GameState::EndedLoss {
-            egui::Image::new(egui::include_image!("../assets/mine.png")).paint_at(ui, rect);
-        } else if sqr.is_flagged && !sqr.is_mine() && self.game_state == GameState::EndedLoss {
-            ui.painter()
-                .rect(rect, 0.0, misflagged_color, Stroke::new(0.5, border_color));
-            egui::Image::new(egui::include_image!("../assets/flag.png")).paint_at(ui, rect);
-        } else if sqr.is_flagged {
-            ui.painter()
-                .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
-            egui::Image::new(egui::include_image!("../assets/flag.png")).paint_at(ui, rect);
-        } else if sqr.is_revealed {
-            match sqr.numeral {
-                1 => egui::Image::new(egui::include_image!("../assets/1.png")).paint_at(ui, rect),
-                2 => egui::Image::new(egui::include_image!("../assets/2.png")).paint_at(ui, rect),
-                3 => egui::Image::new(egui::include_image!("../assets/3.png")).paint_at(ui, rect),
-                4 => egui::Image::new(egui::include_image!("../assets/4.png")).paint_at(ui, rect),
-                5 => egui::Image::new(egui::include_image!("../assets/5.png")).paint_at(ui, rect),
-                6 => egui::Image::new(egui::include_image!("../assets/6.png")).paint_at(ui, rect),
-                7 => egui::Image::new(egui::include_image!("../assets/7.png")).paint_at(ui, rect),
-                8 => egui::Image::new(egui::include_image!("../assets/8.png")).paint_at(ui, rect),
-                _ => {}
-            };
-        } else {
-            ui.painter()
-                .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
+        let fill = |color: Color32, stroke: Stroke| match hexagon {
+            Some(corners) => {
+                ui.painter()
+                    .add(egui::Shape::convex_polygon(corners.to_vec(), color, stroke));
+            }
+            None => {
+                ui.painter().rect(rect, 0.0, color, stroke);
+            }
+        };
+        // The bevelled square backgrounds don't fit a hexagon
+        let background = |tile: SkinTile, tint: Color32| {
+            if hexagon.is_none() {
+                self.paint_tile(ui, rect, tile, tint);
+            }
+        };
+
+        // Background
+        match tile {
+            Tile::Unrevealed | Tile::Flagged | Tile::Questioned | Tile::FlagCorrect => {
+                fill(unrevealed_color, Stroke::NONE);
+                background(SkinTile::Unrevealed, unrevealed_color);
+            }
+            Tile::MisFlagged => {
+                fill(misflagged_color, Stroke::NONE);
+            }
+            Tile::Detonated => {
+                fill(self.palette.detonated, Stroke::NONE);
+                self.paint_tile(ui, rect, SkinTile::Blast, self.palette.detonated);
+            }
+            Tile::Blasted => {
+                fill(revealed_color, Stroke::NONE);
+                if self.state.renderer == TileRenderer::Vector {
+                    fill(self.palette.detonated.gamma_multiply(0.5), Stroke::NONE);
+                } else if let Some(t) = &self.textures {
+                    t.paint_faded(ui, rect, SkinTile::Blast, 128);
+                }
+            }
+            Tile::Pressed | Tile::Blank | Tile::Numeral(_) | Tile::Mine => {
+                fill(revealed_color, Stroke::NONE);
+                background(SkinTile::Blank, revealed_color);
+            }
+        };
+
+        // Foreground
+        let flag_color = self.palette.flag;
+        match tile {
+            Tile::Flagged => {
+                self.paint_tile(ui, rect, SkinTile::Flag, flag_color);
+            }
+            Tile::Questioned => {
+                self.paint_tile(ui, rect, SkinTile::Question, Color32::WHITE);
+            }
+            Tile::FlagCorrect => {
+                self.paint_tile(ui, rect, SkinTile::Flag, flag_color);
+                self.paint_tile(ui, rect, SkinTile::FlagCorrect, Color32::WHITE);
+            }
+            Tile::MisFlagged => {
+                self.paint_tile(ui, rect, SkinTile::Flag, flag_color);
+                self.paint_tile(ui, rect, SkinTile::MisFlag, Color32::WHITE);
+            }
+            Tile::Mine | Tile::Detonated | Tile::Blasted => {
+                self.paint_tile(ui, rect, SkinTile::Mine, Color32::WHITE);
+            }
+            Tile::Numeral(n) => {
+                let color = self.palette.numerals[(n as usize - 1).min(7)];
+                self.paint_tile(ui, rect, SkinTile::Numeral(n), color);
+            }
+            Tile::Unrevealed | Tile::Pressed | Tile::Blank => {}
+        };
+
+        let flash = self.animations.flash(coord.x, coord.y, t);
+        if flash > 0.0 {
+            fill(
+                Color32::from_white_alpha((flash * 110.0) as u8),
+                Stroke::NONE,
+            );
+        }
+        if let Some(c) = self.animations.celebration_color(coord.x, coord.y, t) {
+            fill(c, Stroke::NONE);
         }
 
+        fill(Color32::TRANSPARENT, Stroke::new(0.5, border_color));
+
         if opaque && self.game_state == GameState::Playing {
-            ui.painter()
-                .rect(rect, 0.0, opaque_color, Stroke::new(0.5, border_color));
+            fill(opaque_color, Stroke::new(0.5, border_color));
         }
-        response
     }
 }
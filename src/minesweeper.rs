@@ -1,6 +1,10 @@
 use anyhow::Result;
 use itertools::iproduct;
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::boardcode::{BoardCode, BoardLayout, MAX_DIMENSION};
 
 /// Indicates some sort of error related to initialization and play on the gameboard
 #[derive(Debug)]
@@ -11,6 +15,7 @@ pub enum Error {
     IndexOutOfBounds,
     InvalidCascade,
     UnexpectedResult,
+    InvalidLayout,
 }
 
 /// Represents the type of a square as to the presence of a mine
@@ -20,11 +25,20 @@ pub enum SquareType {
     Mine,
 }
 
+/// How the player has marked a hidden square
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Marking {
+    #[default]
+    None,
+    Flag,
+    Question,
+}
+
 /// Representation of a single minesweeper square.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Square {
     pub is_revealed: bool,
-    pub is_flagged: bool,
+    pub marking: Marking,
     pub square_type: SquareType,
     pub numeral: u32,
 }
@@ -33,7 +47,7 @@ impl Default for Square {
     fn default() -> Self {
         Square {
             is_revealed: false,
-            is_flagged: false,
+            marking: Marking::None,
             numeral: 0,
             square_type: SquareType::Empty,
         }
@@ -44,7 +58,7 @@ impl Square {
     pub fn default_mine() -> Self {
         Square {
             is_revealed: false,
-            is_flagged: false,
+            marking: Marking::None,
             numeral: 0,
             square_type: SquareType::Mine,
         }
@@ -54,18 +68,63 @@ impl Square {
         self.square_type == SquareType::Mine
     }
 
+    pub fn is_flagged(&self) -> bool {
+        self.marking == Marking::Flag
+    }
+
+    pub fn is_questioned(&self) -> bool {
+        self.marking == Marking::Question
+    }
+
     #[allow(dead_code)]
-    pub fn print(&self) {
-        if self.is_flagged {
-            print!(" > ");
+    /// The square as three characters of a text board, as written by `print`
+    pub fn to_text(self) -> String {
+        if self.is_flagged() {
+            " > ".to_string()
+        } else if self.is_questioned() {
+            " ? ".to_string()
         } else if !self.is_revealed {
-            print!(" - ");
+            " - ".to_string()
         } else if self.is_mine() {
-            print!(" X ");
+            " X ".to_string()
         } else if self.numeral > 0 {
-            print!(" {} ", self.numeral)
+            format!(" {} ", self.numeral)
         } else {
-            print!("   ");
+            "   ".to_string()
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn print(&self) {
+        print!("{}", self.to_text());
+    }
+
+    /// Reads a square written by `to_text`. Only squares shown as `X` are mines,
+    /// since the text doesn't say what's under hidden squares.
+    fn from_text(cell: &[char]) -> Result<Self, Error> {
+        let revealed = |numeral, square_type| Square {
+            is_revealed: true,
+            numeral,
+            square_type,
+            ..Default::default()
+        };
+        match cell {
+            [' ', c, ' '] => match c {
+                '>' => Ok(Square {
+                    marking: Marking::Flag,
+                    ..Default::default()
+                }),
+                '?' => Ok(Square {
+                    marking: Marking::Question,
+                    ..Default::default()
+                }),
+                '-' => Ok(Square::default()),
+                'X' => Ok(revealed(0, SquareType::Mine)),
+                ' ' => Ok(revealed(0, SquareType::Empty)),
+                '1'..='8' => Ok(revealed(c.to_digit(10).unwrap_or(0), SquareType::Empty)),
+                _ => Err(Error::InvalidLayout),
+            },
+            _ => Err(Error::InvalidLayout),
         }
     }
 }
@@ -93,11 +152,213 @@ impl Coordinate {
             .sqrt()
     }
 
+    #[allow(dead_code)]
     pub fn near(&self, coord: &Coordinate) -> bool {
         self.distance(coord) <= 1.5
     }
 }
 
+/// Which squares touch each other, and so count toward numerals and open together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum Neighborhood {
+    /// The eight squares around a square, as in the classic game
+    #[default]
+    Square,
+
+    /// Six hexagons. The board is laid out in rows, with odd rows shifted right by
+    /// half a hexagon.
+    Hex,
+
+    /// The eight squares a chess knight's move away, so a square doesn't touch
+    /// any of those beside it
+    Knight,
+
+    /// The four squares above, below, left and right, but not those diagonal
+    Orthogonal,
+}
+
+impl Neighborhood {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Neighborhood::Square => "Square",
+            Neighborhood::Hex => "Hexagonal",
+            Neighborhood::Knight => "Knight's move",
+            Neighborhood::Orthogonal => "Orthogonal",
+        }
+    }
+
+    /// The name used in board codes and leaderboard files
+    pub fn key(&self) -> &'static str {
+        match self {
+            Neighborhood::Square => "square",
+            Neighborhood::Hex => "hex",
+            Neighborhood::Knight => "knight",
+            Neighborhood::Orthogonal => "orthogonal",
+        }
+    }
+
+    pub fn all() -> [Neighborhood; 4] {
+        [
+            Neighborhood::Square,
+            Neighborhood::Hex,
+            Neighborhood::Knight,
+            Neighborhood::Orthogonal,
+        ]
+    }
+
+    /// The most squares kept clear around a first click: the square and all of those
+    /// it touches
+    pub fn clear_area(&self) -> u32 {
+        self.offsets(0).len() as u32
+    }
+
+    /// Whether a board of this neighborhood can wrap around its edges. Hexagonal
+    /// rows alternate their shift, so they'd only meet up on a board with an even
+    /// number of rows.
+    pub fn can_wrap(&self) -> bool {
+        match self {
+            Neighborhood::Square | Neighborhood::Knight | Neighborhood::Orthogonal => true,
+            Neighborhood::Hex => false,
+        }
+    }
+
+    /// The offsets from a square in row `y` to the squares it touches, and to
+    /// itself
+    fn offsets(&self, y: u32) -> Vec<(i32, i32)> {
+        match self {
+            Neighborhood::Square => iproduct!(-1_i32..2_i32, -1_i32..2_i32).collect(),
+            Neighborhood::Hex => {
+                // The rows above and below are shifted half a hexagon from this one
+                let shift = (y % 2) as i32;
+                vec![
+                    (shift - 1, -1),
+                    (shift, -1),
+                    (-1, 0),
+                    (0, 0),
+                    (1, 0),
+                    (shift - 1, 1),
+                    (shift, 1),
+                ]
+            }
+            Neighborhood::Knight => vec![
+                (-1, -2),
+                (1, -2),
+                (-2, -1),
+                (2, -1),
+                (0, 0),
+                (-2, 1),
+                (2, 1),
+                (-1, 2),
+                (1, 2),
+            ],
+            Neighborhood::Orthogonal => vec![(0, -1), (-1, 0), (0, 0), (1, 0), (0, 1)],
+        }
+    }
+}
+
+/// How the edges of the board meet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum Topology {
+    /// Squares along an edge have nothing beyond it, as in the classic game
+    #[default]
+    Bounded,
+
+    /// Each edge wraps around to the opposite one, so every square has a full set
+    /// of neighbors
+    Torus,
+}
+
+impl Topology {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topology::Bounded => "Bounded",
+            Topology::Torus => "Wrapped (torus)",
+        }
+    }
+
+    pub fn all() -> [Topology; 2] {
+        [Topology::Bounded, Topology::Torus]
+    }
+}
+
+/// The kind of board a game is played on: which squares touch, and whether the
+/// edges wrap. Each kind is ranked on its own leaderboards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Variant {
+    pub neighborhood: Neighborhood,
+    pub topology: Topology,
+}
+
+impl Variant {
+    /// The variant of a neighborhood and topology. Neighborhoods that can't wrap are
+    /// kept bounded.
+    pub fn new(neighborhood: Neighborhood, topology: Topology) -> Self {
+        Variant {
+            neighborhood,
+            topology: if neighborhood.can_wrap() {
+                topology
+            } else {
+                Topology::Bounded
+            },
+        }
+    }
+
+    /// Whether this is the board of the classic game
+    pub fn is_classic(&self) -> bool {
+        *self == Variant::default()
+    }
+
+    pub fn as_str(&self) -> String {
+        match (self.neighborhood, self.topology) {
+            (n, Topology::Bounded) => n.as_str().to_string(),
+            (Neighborhood::Square, Topology::Torus) => "Torus".to_string(),
+            (n, Topology::Torus) => format!("{} torus", n.as_str()),
+        }
+    }
+
+    /// The name used in board codes and leaderboard files, such as `hex` or `torus`
+    pub fn key(&self) -> String {
+        match (self.neighborhood, self.topology) {
+            (n, Topology::Bounded) => n.key().to_string(),
+            (Neighborhood::Square, Topology::Torus) => "torus".to_string(),
+            (n, Topology::Torus) => format!("{}-torus", n.key()),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Variant::all().into_iter().find(|v| v.key() == key)
+    }
+
+    /// Every variant that can be played, the classic board first
+    pub fn all() -> Vec<Variant> {
+        iproduct!(Topology::all(), Neighborhood::all())
+            .map(|(topology, neighborhood)| Variant {
+                neighborhood,
+                topology,
+            })
+            .filter(|v| v.topology == Topology::Bounded || v.neighborhood.can_wrap())
+            .collect()
+    }
+
+    /// The square at a position on a board of a size. Positions past the edge of a
+    /// torus wrap around to the other side, and those off a bounded board are None.
+    pub fn wrap(&self, x: i32, y: i32, width: u32, height: u32) -> Option<Coordinate> {
+        let (w, h) = (width as i32, height as i32);
+        match self.topology {
+            Topology::Bounded if x < 0 || y < 0 || x >= w || y >= h => None,
+            Topology::Bounded => Some(Coordinate {
+                x: x as u32,
+                y: y as u32,
+            }),
+            Topology::Torus if w == 0 || h == 0 => None,
+            Topology::Torus => Some(Coordinate {
+                x: x.rem_euclid(w) as u32,
+                y: y.rem_euclid(h) as u32,
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum RevealType {
     #[default]
@@ -111,6 +372,7 @@ pub enum RevealType {
 #[derive(Debug, PartialEq, Eq)]
 pub enum PlayResult {
     Flagged(bool),
+    Questioned(bool),
     Explosion(Coordinate), // Loss
     NoChange,
     Revealed(Coordinate),
@@ -125,6 +387,23 @@ pub struct GameBoard {
     pub num_mines: u32,
     pub squares: Vec<Square>,
     pub is_populated: bool,
+
+    /// Seeds the placement of mines. The same seed and first click always produce
+    /// the same board.
+    pub seed: u64,
+
+    /// The square, and its neighbors, kept clear of mines when the board was
+    /// populated
+    pub first_click: Option<Coordinate>,
+
+    /// Flag plays cycle flag -> question -> none rather than flag -> none
+    pub use_question_marks: bool,
+
+    /// Question marked squares are protected from reveals the same as flags
+    pub question_blocks_reveal: bool,
+
+    /// Which squares touch, and whether the edges wrap
+    pub variant: Variant,
 }
 
 impl GameBoard {
@@ -135,6 +414,11 @@ impl GameBoard {
             num_mines: 0,
             squares: (0..width * height).map(|_| Square::default()).collect(),
             is_populated: false,
+            seed: rand::random(),
+            first_click: None,
+            use_question_marks: false,
+            question_blocks_reveal: false,
+            variant: Variant::default(),
         }
     }
 
@@ -166,6 +450,49 @@ impl GameBoard {
         Ok(gb)
     }
 
+    /// Builds a board with mines on exactly the squares marked in `mines`, given in
+    /// row order
+    pub fn new_with_layout(width: u32, height: u32, mines: &[bool]) -> Result<GameBoard, Error> {
+        if mines.len() != (width * height) as usize {
+            return Err(Error::InvalidLayout);
+        }
+        let mut gb = Self::new(width, height);
+        mines
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| **m)
+            .for_each(|(idx, _)| gb.squares[idx] = Square::default_mine());
+        gb.num_mines = mines.iter().filter(|m| **m).count() as u32;
+        gb.is_populated = true;
+        gb.populate_numerals()?;
+        Ok(gb)
+    }
+
+    /// Builds the board a shared board code describes. A seeded code without a first
+    /// click gives an unpopulated board whose mines are placed on the first click.
+    pub fn from_board_code(code: &BoardCode) -> Result<GameBoard, Error> {
+        match &code.layout {
+            BoardLayout::Seed(seed) => {
+                let mut gb = Self::new(code.width, code.height);
+                gb.seed = *seed;
+                gb.variant = code.variant;
+                gb.num_mines = code.num_mines;
+                if let Some(fc) = &code.first_click {
+                    gb.populate_mines_around(code.num_mines, Some(fc.clone()))?;
+                    gb.populate_numerals()?;
+                }
+                Ok(gb)
+            }
+            BoardLayout::Mines(mines) => {
+                let mut gb = Self::new_with_layout(code.width, code.height, mines)?;
+                gb.variant = code.variant;
+                gb.populate_numerals()?;
+                gb.first_click = code.first_click.clone();
+                Ok(gb)
+            }
+        }
+    }
+
     /// Convert x, y coordinate to vector index
     fn xy_to_idx(&self, x: u32, y: u32) -> u32 {
         y * self.width + x
@@ -207,32 +534,76 @@ impl GameBoard {
         self.get_square(coord.x, coord.y)
     }
 
+    /// The square at a position, which on a torus may be past an edge. None if it's
+    /// off the board.
+    fn wrap(&self, x: i32, y: i32) -> Option<Coordinate> {
+        self.variant.wrap(x, y, self.width, self.height)
+    }
+
+    /// The positions of the squares touching a square, and of the square itself.
+    /// Positions off the board are included, and are ignored by the protected
+    /// lookups, or wrapped around to the other side on a torus. A torus narrower
+    /// than the neighborhood reaches some squares both ways round, but only one
+    /// position is given for each.
+    fn around(&self, x: u32, y: u32) -> Vec<(i32, i32)> {
+        let mut reached = vec![];
+        self.variant
+            .neighborhood
+            .offsets(y)
+            .into_iter()
+            .map(|(dx, dy)| (x as i32 + dx, y as i32 + dy))
+            .filter(|(nx, ny)| match self.wrap(*nx, *ny) {
+                Some(c) if reached.contains(&c) => false,
+                Some(c) => {
+                    reached.push(c);
+                    true
+                }
+                None => true,
+            })
+            .collect()
+    }
+
+    /// The squares touching a square, and the square itself, on the board or
+    /// wrapped around it
+    pub fn neighbors(&self, c: &Coordinate) -> Vec<Coordinate> {
+        self.around(c.x, c.y)
+            .into_iter()
+            .filter_map(|(x, y)| self.wrap(x, y))
+            .collect()
+    }
+
+    /// Whether two squares touch, or are the same square
+    pub fn is_near(&self, a: &Coordinate, b: &Coordinate) -> bool {
+        self.around(a.x, a.y)
+            .into_iter()
+            .any(|(x, y)| self.wrap(x, y).as_ref() == Some(b))
+    }
+
+    /// The distance between the centres of two squares, in squares. On a torus it's
+    /// measured the short way around.
+    pub fn distance(&self, a: &Coordinate, b: &Coordinate) -> f32 {
+        let span = |p: u32, q: u32, size: u32| {
+            let d = p.abs_diff(q);
+            match self.variant.topology {
+                Topology::Torus if d <= size => d.min(size - d),
+                _ => d,
+            }
+        };
+        (span(a.x, b.x, self.width) as f32).hypot(span(a.y, b.y, self.height) as f32)
+    }
+
     /// Determines whether a square contains a mine, allowing for negative
-    /// and invalid coordinates.
+    /// and invalid coordinates, which wrap around on a torus.
     fn is_mine_protected(&self, x: i32, y: i32) -> bool {
-        if x < 0 {
-            return false;
-        }
-        if y < 0 {
-            return false;
-        }
-
-        match self.get_square(x as u32, y as u32) {
-            Ok(sqr) => sqr.is_mine(),
+        match self.wrap(x, y).map(|c| self.get_square_by_coordinate(&c)) {
+            Some(Ok(sqr)) => sqr.is_mine(),
             _ => false,
         }
     }
 
     fn is_flagged_protected(&self, x: i32, y: i32) -> bool {
-        if x < 0 {
-            return false;
-        }
-        if y < 0 {
-            return false;
-        }
-
-        match self.get_square(x as u32, y as u32) {
-            Ok(sqr) => sqr.is_flagged,
+        match self.wrap(x, y).map(|c| self.get_square_by_coordinate(&c)) {
+            Some(Ok(sqr)) => sqr.is_flagged(),
             _ => false,
         }
     }
@@ -241,9 +612,11 @@ impl GameBoard {
         if x >= self.width || y >= self.height {
             Err(Error::InvalidCoordinates)
         } else {
-            Ok(iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-                .map(|(dx, dy)| {
-                    if self.is_flagged_protected(x as i32 + dx, y as i32 + dy) {
+            Ok(self
+                .around(x, y)
+                .into_iter()
+                .map(|(nx, ny)| {
+                    if self.is_flagged_protected(nx, ny) {
                         1
                     } else {
                         0
@@ -260,25 +633,31 @@ impl GameBoard {
         if x >= self.width || y >= self.height {
             Err(Error::InvalidCoordinates)
         } else {
-            Ok(iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-                .map(|(dx, dy)| {
-                    if self.is_mine_protected(x as i32 + dx, y as i32 + dy) {
-                        1
-                    } else {
-                        0
-                    }
-                })
+            Ok(self
+                .around(x, y)
+                .into_iter()
+                .map(
+                    |(nx, ny)| {
+                        if self.is_mine_protected(nx, ny) {
+                            1
+                        } else {
+                            0
+                        }
+                    },
+                )
                 .collect::<Vec<u32>>()
                 .into_iter()
                 .sum())
         }
     }
 
-    fn gen_random_square_coordinates(&self) -> Coordinate {
-        Coordinate {
-            x: rand::thread_rng().gen_range(0..self.width),
-            y: rand::thread_rng().gen_range(0..self.height),
-        }
+    /// The generator mines are placed with. Seeds are kept in board codes, replays
+    /// and history, so the generator is pinned rather than rand's `StdRng`, which
+    /// may change between versions, and the seed is laid out by hand.
+    fn seeded_rng(seed: u64) -> ChaCha8Rng {
+        let mut bytes = [0; 32];
+        bytes[..8].copy_from_slice(&seed.to_le_bytes());
+        ChaCha8Rng::from_seed(bytes)
     }
 
     pub fn populate_mines_around(
@@ -286,33 +665,49 @@ impl GameBoard {
         num_mines: u32,
         keep_clear: Option<Coordinate>,
     ) -> Result<(), Error> {
-        if num_mines > self.width * self.height {
+        // Squares around the first click can't take a mine
+        let mut candidates = iproduct!(0..self.height, 0..self.width)
+            .map(|(y, x)| Coordinate { x, y })
+            .filter(|c| {
+                keep_clear
+                    .as_ref()
+                    .map(|kc| !self.is_near(kc, c))
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<Coordinate>>();
+        if num_mines as usize > candidates.len() {
             Err(Error::ExcessiveMines)
         } else {
             self.num_mines = num_mines;
-
-            let mut mines_placed = 0;
-            while mines_placed < num_mines {
-                let random_coord = self.gen_random_square_coordinates();
-
-                if let Some(kc) = &keep_clear {
-                    let sqr = self.get_square_by_coordinate(&random_coord)?;
-                    if !kc.near(&random_coord) && !sqr.is_mine() {
-                        let idx = self.coordinate_to_idx(&random_coord);
-                        self.squares[idx as usize] = Square::default_mine();
-                        mines_placed += 1;
-                    }
-                } else {
-                    let idx = self.coordinate_to_idx(&random_coord);
-                    self.squares[idx as usize] = Square::default_mine();
-                    mines_placed += 1;
-                }
-            }
+            self.first_click = keep_clear.clone();
+
+            // A Fisher-Yates shuffle of just as many squares as there are mines. Each
+            // pick scales a random u64 into the squares left, so the layout depends
+            // only on the generator's output.
+            let mut rng = GameBoard::seeded_rng(self.seed);
+            let n = candidates.len() as u64;
+            (0..num_mines as u64).for_each(|i| {
+                let j = i + ((rng.next_u64() as u128 * (n - i) as u128) >> 64) as u64;
+                candidates.swap(i as usize, j as usize);
+            });
+            candidates[..num_mines as usize]
+                .iter()
+                .for_each(|c| self.place_mine(c));
             self.is_populated = true;
             Ok(())
         }
     }
 
+    /// Places a mine, keeping any flag or question mark placed before the board was
+    /// populated
+    fn place_mine(&mut self, coord: &Coordinate) {
+        let idx = self.coordinate_to_idx(coord) as usize;
+        self.squares[idx] = Square {
+            marking: self.squares[idx].marking,
+            ..Square::default_mine()
+        };
+    }
+
     pub fn populate_mines(&mut self, num_mines: u32) -> Result<(), Error> {
         self.populate_mines_around(num_mines, None)
     }
@@ -328,12 +723,107 @@ impl GameBoard {
 
     #[allow(dead_code)]
     pub fn print(&self) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                self.squares[self.xy_to_idx(x, y) as usize].print();
+        print!("{}", self.to_text());
+    }
+
+    /// The board as text, one line per row and three characters per square
+    pub fn to_text(&self) -> String {
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| self.squares[self.xy_to_idx(x, y) as usize].to_text())
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+
+    /// Reads a board written by `to_text`, restoring how each square was shown.
+    /// Trailing spaces may be missing, as editors often strip them, though a
+    /// column of blank squares down the right hand side is then lost too.
+    pub fn from_text(text: &str) -> Result<GameBoard, Error> {
+        let rows = text
+            .lines()
+            .map(|l| l.trim_end_matches('\r').chars().collect::<Vec<char>>())
+            .collect::<Vec<Vec<char>>>();
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0).div_ceil(3) as u32;
+        let height = rows.len() as u32;
+        if width == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(Error::InvalidLayout);
+        }
+
+        let mut gb = Self::new(width, height);
+        gb.squares = rows
+            .iter()
+            .flat_map(|r| {
+                let mut r = r.clone();
+                r.resize(width as usize * 3, ' ');
+                r.chunks(3)
+                    .map(Square::from_text)
+                    .collect::<Vec<Result<Square, Error>>>()
+            })
+            .collect::<Result<Vec<Square>, Error>>()?;
+        gb.num_mines = gb.squares.iter().filter(|s| s.is_mine()).count() as u32;
+        gb.is_populated = true;
+
+        // Numerals shown on revealed squares are kept, as there may be mines under
+        // hidden squares
+        let shown = gb.squares.clone();
+        gb.populate_numerals()?;
+        gb.squares
+            .iter_mut()
+            .zip(shown.iter())
+            .filter(|(_, shown)| shown.is_revealed && !shown.is_mine())
+            .for_each(|(s, shown)| s.numeral = shown.numeral);
+        Ok(gb)
+    }
+
+    /// Writes the layout as an MBF board file, the format of Minesweeper X and
+    /// Arbiter: the width and height as a byte each, the mine count as a big endian
+    /// 16 bit integer, then each mine's x and y as a byte each.
+    pub fn to_mbf(&self) -> Result<Vec<u8>, Error> {
+        let mines = iproduct!(0..self.height, 0..self.width)
+            .filter(|(y, x)| self.squares[self.xy_to_idx(*x, *y) as usize].is_mine())
+            .collect::<Vec<(u32, u32)>>();
+        if self.width > u8::MAX as u32
+            || self.height > u8::MAX as u32
+            || mines.len() > u16::MAX as usize
+        {
+            return Err(Error::InvalidLayout);
+        }
+
+        let mut bytes = vec![self.width as u8, self.height as u8];
+        bytes.extend((mines.len() as u16).to_be_bytes());
+        mines
+            .iter()
+            .for_each(|(y, x)| bytes.extend([*x as u8, *y as u8]));
+        Ok(bytes)
+    }
+
+    /// Reads an MBF board file, as written by `to_mbf`
+    pub fn from_mbf(bytes: &[u8]) -> Result<GameBoard, Error> {
+        let [width, height, n_hi, n_lo, mine_bytes @ ..] = bytes else {
+            return Err(Error::InvalidLayout);
+        };
+        let (width, height) = (*width as u32, *height as u32);
+        let num_mines = u16::from_be_bytes([*n_hi, *n_lo]) as usize;
+        if width == 0 || height == 0 || mine_bytes.len() != num_mines * 2 {
+            return Err(Error::InvalidLayout);
+        }
+
+        let mut mines = vec![false; (width * height) as usize];
+        for xy in mine_bytes.chunks(2) {
+            let (x, y) = (xy[0] as u32, xy[1] as u32);
+            if x >= width || y >= height {
+                return Err(Error::InvalidCoordinates);
+            }
+            let idx = (y * width + x) as usize;
+            if mines[idx] {
+                return Err(Error::InvalidLayout);
             }
-            println!();
+            mines[idx] = true;
         }
+        Self::new_with_layout(width, height, &mines)
     }
 
     /// Toggles the flagged state of a square.
@@ -341,15 +831,27 @@ impl GameBoard {
     ///
     /// A revealed square cannot be flagged
     ///
+    /// When question marks are in use, the marking cycles flag -> question -> none
+    /// and the updated question state is returned once the flag is removed.
     pub fn flag(&mut self, x: u32, y: u32) -> Result<PlayResult, Error> {
         if x >= self.width || y >= self.height {
             Err(Error::InvalidCoordinates)
         } else {
             let idx = self.xy_to_idx(x, y);
             let sqr = self.get_square_by_idx(idx)?;
-            if !sqr.is_revealed {
-                self.squares[idx as usize].is_flagged = !sqr.is_flagged;
-                Ok(PlayResult::Flagged(self.squares[idx as usize].is_flagged))
+            if !sqr.is_revealed && sqr.is_questioned() {
+                self.squares[idx as usize].marking = Marking::None;
+                Ok(PlayResult::Questioned(false))
+            } else if !sqr.is_revealed && sqr.is_flagged() && self.use_question_marks {
+                self.squares[idx as usize].marking = Marking::Question;
+                Ok(PlayResult::Questioned(true))
+            } else if !sqr.is_revealed {
+                self.squares[idx as usize].marking = if sqr.is_flagged() {
+                    Marking::None
+                } else {
+                    Marking::Flag
+                };
+                Ok(PlayResult::Flagged(self.squares[idx as usize].is_flagged()))
             } else {
                 Ok(PlayResult::NoChange) // Maybe return false instead?
             }
@@ -364,15 +866,17 @@ impl GameBoard {
         let idx = self.xy_to_idx(x, y);
 
         if self.squares[idx as usize].is_mine()
-            || self.squares[idx as usize].is_flagged
+            || self.squares[idx as usize].is_flagged()
             || self.squares[idx as usize].numeral > 0
         {
             return Err(Error::InvalidCascade);
         }
         self.squares[idx as usize].is_revealed = true;
 
-        let results = iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-            .map(|(dx, dy)| self.reveal_protected(x as i32 + dx, y as i32 + dy))
+        let results = self
+            .around(x, y)
+            .into_iter()
+            .map(|(nx, ny)| self.reveal_protected(nx, ny))
             .collect::<Vec<PlayResult>>();
 
         Ok(PlayResult::CascadedReveal(results))
@@ -386,12 +890,17 @@ impl GameBoard {
             let idx = self.xy_to_idx(x, y);
             let sqr = self.get_square_by_idx(idx)?;
 
-            if sqr.is_mine() && !sqr.is_flagged {
+            if sqr.is_questioned() && self.question_blocks_reveal {
+                // Question marks are protecting the square the same as a flag would
+                Ok(PlayResult::NoChange)
+            } else if sqr.is_mine() && !sqr.is_flagged() {
                 // If the square is a mine and it's not flagged (unprotected)
                 self.squares[idx as usize].is_revealed = true;
+                self.squares[idx as usize].marking = Marking::None;
                 Ok(PlayResult::Explosion(Coordinate::from((x, y))))
-            } else if !sqr.is_mine() && !sqr.is_flagged && !sqr.is_revealed {
+            } else if !sqr.is_mine() && !sqr.is_flagged() && !sqr.is_revealed {
                 // if the square is not a mine, is unflagged, and is unrevealed
+                self.squares[idx as usize].marking = Marking::None;
                 if self.squares[idx as usize].numeral == 0 {
                     // If it's a non-numeral square, we can auto-chord it
                     self.cascade_from(x, y)
@@ -408,15 +917,10 @@ impl GameBoard {
     }
 
     fn reveal_protected(&mut self, x: i32, y: i32) -> PlayResult {
-        if x < 0 {
-            return PlayResult::NoChange;
-        }
-        if y < 0 {
-            return PlayResult::NoChange;
+        match self.wrap(x, y) {
+            Some(c) => self.reveal(c.x, c.y).unwrap_or(PlayResult::NoChange),
+            None => PlayResult::NoChange,
         }
-
-        self.reveal(x as u32, y as u32)
-            .unwrap_or(PlayResult::NoChange)
     }
 
     /// Determine whether a given square can be chorded.
@@ -447,8 +951,10 @@ impl GameBoard {
         } else if !self.can_chord_square(x, y)? {
             Ok(PlayResult::NoChange)
         } else {
-            let results = iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-                .map(|(dx, dy)| self.reveal_protected(x as i32 + dx, y as i32 + dy))
+            let results = self
+                .around(x, y)
+                .into_iter()
+                .map(|(nx, ny)| self.reveal_protected(nx, ny))
                 .collect::<Vec<PlayResult>>();
 
             Ok(PlayResult::CascadedReveal(results))
@@ -511,7 +1017,7 @@ impl GameBoard {
         self.squares
             .clone()
             .into_iter()
-            .map(|s| if s.is_flagged { 1 } else { 0 })
+            .map(|s| if s.is_flagged() { 1 } else { 0 })
             .collect::<Vec<u32>>()
             .into_iter()
             .sum::<u32>()
@@ -527,18 +1033,67 @@ impl GameBoard {
             .sum::<u32>()
     }
 
+    /// The Bechtel's Board Benchmark Value (3BV): the minimum number of clicks
+    /// needed to clear the board without chording. Each opening counts once, as does
+    /// every numbered square not on the edge of an opening.
+    pub fn three_bv(&self) -> u32 {
+        let mut marked = vec![false; self.squares.len()];
+        let mut bbbv = 0;
+
+        // Openings, marking each along with the numbered squares around its edge
+        (0..self.squares.len()).for_each(|idx| {
+            let sqr = self.squares[idx];
+            if marked[idx] || sqr.is_mine() || sqr.numeral > 0 {
+                return;
+            }
+            bbbv += 1;
+            marked[idx] = true;
+            let mut stack = vec![idx as u32];
+            while let Some(i) = stack.pop() {
+                let Ok(c) = self.idx_to_xy(i) else {
+                    continue;
+                };
+                self.around(c.x, c.y).into_iter().for_each(|(nx, ny)| {
+                    let Some(nc) = self.wrap(nx, ny) else {
+                        return;
+                    };
+                    let n = self.coordinate_to_idx(&nc);
+                    let nsqr = self.squares[n as usize];
+                    if !marked[n as usize] && !nsqr.is_mine() {
+                        marked[n as usize] = true;
+                        if nsqr.numeral == 0 {
+                            stack.push(n);
+                        }
+                    }
+                });
+            }
+        });
+
+        // Numbered squares that have to be clicked individually
+        bbbv + self
+            .squares
+            .iter()
+            .zip(marked)
+            .filter(|(s, m)| !m && !s.is_mine())
+            .count() as u32
+    }
+
     // Don't cheat
     #[allow(dead_code)]
     pub fn flag_all_mines(&mut self) {
         for sqr in self.squares.iter_mut() {
-            sqr.is_flagged = sqr.is_mine();
+            sqr.marking = if sqr.is_mine() {
+                Marking::Flag
+            } else {
+                Marking::None
+            };
         }
     }
 
     #[allow(dead_code)]
     pub fn reset_existing(&mut self) {
         for sqr in self.squares.iter_mut() {
-            sqr.is_flagged = false;
+            sqr.marking = Marking::None;
             sqr.is_revealed = false;
         }
     }
@@ -549,12 +1104,12 @@ fn test_squares() {
     let sq1 = Square::default();
     assert!(!sq1.is_mine());
     assert!(!sq1.is_revealed);
-    assert!(!sq1.is_flagged);
+    assert!(!sq1.is_flagged());
 
     let sq1 = Square::default_mine();
     assert!(sq1.is_mine());
     assert!(!sq1.is_revealed);
-    assert!(!sq1.is_flagged);
+    assert!(!sq1.is_flagged());
 }
 
 #[test]
@@ -610,6 +1165,26 @@ fn test_excessive_mines() {
     }
 }
 
+#[test]
+fn test_excessive_mines_around_first_click() {
+    // The first click and its neighbors are kept clear
+    let mut gb = GameBoard::new(3, 3);
+    match gb.populate_mines_around(1, Some(Coordinate { x: 1, y: 1 })) {
+        Err(Error::ExcessiveMines) => {}
+        _ => panic!("Invalid response"),
+    }
+    assert!(gb
+        .populate_mines_around(5, Some(Coordinate { x: 0, y: 0 }))
+        .is_ok());
+
+    // Counts too large to add up are refused rather than overflowing
+    let mut gb = GameBoard::new(3, 3);
+    assert!(matches!(
+        gb.populate_mines_around(u32::MAX, Some(Coordinate { x: 0, y: 0 })),
+        Err(Error::ExcessiveMines)
+    ));
+}
+
 #[test]
 fn test_invalid_coordinates() {
     let gb = GameBoard::new(2, 2);
@@ -846,6 +1421,50 @@ fn test_simple_game_2() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_question_marks() -> Result<(), Error> {
+    let mut gb = GameBoard::new(3, 3);
+    gb.squares[0] = Square::default_mine();
+    gb.populate_numerals()?;
+    gb.use_question_marks = true;
+
+    // Cycle: flag -> question -> none
+    assert_eq!(gb.play(0, 0, RevealType::Flag)?, PlayResult::Flagged(true));
+    assert_eq!(gb.num_flags(), 1);
+    assert_eq!(
+        gb.play(0, 0, RevealType::Flag)?,
+        PlayResult::Questioned(true)
+    );
+    assert!(gb.get_square(0, 0)?.is_questioned());
+    assert!(!gb.get_square(0, 0)?.is_flagged());
+    assert_eq!(gb.num_flags(), 0);
+    assert_eq!(gb.flagged_neighbor_count(1, 1)?, 0);
+    assert_eq!(
+        gb.play(0, 0, RevealType::Flag)?,
+        PlayResult::Questioned(false)
+    );
+    assert!(!gb.get_square(0, 0)?.is_questioned());
+
+    // Question marks block reveals only if configured to
+    gb.play(0, 0, RevealType::Flag)?;
+    gb.play(0, 0, RevealType::Flag)?;
+    gb.question_blocks_reveal = true;
+    assert_eq!(gb.play(0, 0, RevealType::Reveal)?, PlayResult::NoChange);
+    gb.question_blocks_reveal = false;
+    assert_eq!(
+        gb.play(0, 0, RevealType::Reveal)?,
+        PlayResult::Explosion(Coordinate { x: 0, y: 0 })
+    );
+    assert!(!gb.get_square(0, 0)?.is_questioned());
+
+    // Without question marks, flagging toggles as before
+    let mut gb = GameBoard::new(3, 3);
+    assert_eq!(gb.play(1, 1, RevealType::Flag)?, PlayResult::Flagged(true));
+    assert_eq!(gb.play(1, 1, RevealType::Flag)?, PlayResult::Flagged(false));
+
+    Ok(())
+}
+
 #[test]
 fn test_coordinate_is_near() {
     let center = Coordinate { x: 5, y: 5 };
@@ -862,3 +1481,311 @@ fn test_coordinate_is_near() {
 
     assert!(!center.near(&Coordinate { x: 3, y: 5 })); // Left left
 }
+
+#[test]
+fn test_seeded_population() -> Result<(), Error> {
+    let keep_clear = Coordinate { x: 3, y: 3 };
+    let mut a = GameBoard::new(16, 16);
+    a.seed = 1234;
+    a.populate_mines_around(40, Some(keep_clear.clone()))?;
+    let mut b = GameBoard::new(16, 16);
+    b.seed = 1234;
+    b.populate_mines_around(40, Some(keep_clear))?;
+    assert_eq!(a.squares, b.squares);
+
+    // Seeds are stored, so the layout they give must never change
+    let mut c = GameBoard::new(9, 9);
+    c.seed = 1234;
+    c.populate_mines_around(10, Some(Coordinate { x: 4, y: 4 }))?;
+    let mines = iproduct!(0..9, 0..9)
+        .filter(|(y, x)| c.get_square(*x, *y).unwrap().is_mine())
+        .map(|(y, x)| (x, y))
+        .collect::<Vec<(u32, u32)>>();
+    assert_eq!(
+        mines,
+        [
+            (0, 0),
+            (1, 0),
+            (6, 0),
+            (5, 1),
+            (8, 2),
+            (3, 6),
+            (7, 6),
+            (8, 6),
+            (6, 7),
+            (2, 8)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_three_bv() -> Result<(), Error> {
+    // . . 1 * 1    <- the 1 at the far right touches no opening
+    // . . 1 1 1
+    // 1 1 . . .    <- the two openings are joined diagonally
+    // * 1 . . .
+    let mut gb = GameBoard::new(5, 4);
+    [(3, 0), (0, 3)].iter().for_each(|(x, y)| {
+        let idx = gb.xy_to_idx(*x, *y);
+        gb.squares[idx as usize] = Square::default_mine();
+    });
+    gb.num_mines = 2;
+    gb.populate_numerals()?;
+    assert_eq!(gb.three_bv(), 2);
+
+    // A numeral isolated from any opening needs its own click
+    let mut gb = GameBoard::new(3, 1);
+    gb.squares[0] = Square::default_mine();
+    gb.squares[2] = Square::default_mine();
+    gb.num_mines = 2;
+    gb.populate_numerals()?;
+    assert_eq!(gb.three_bv(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_text_round_trip() -> Result<(), Error> {
+    // Seeded, as a right hand column of blank squares would be lost with stripped
+    // trailing spaces
+    let mut gb = crate::replay::test_board();
+    gb.use_question_marks = true;
+    gb.play(4, 4, RevealType::Reveal)?;
+    let hidden = iproduct!(0..9, 0..9)
+        .find(|(x, y)| !gb.get_square(*x, *y).unwrap().is_revealed)
+        .unwrap();
+    gb.play(hidden.0, hidden.1, RevealType::Flag)?;
+    gb.play(hidden.0, hidden.1, RevealType::Flag)?;
+
+    let text = gb.to_text();
+    assert!(text.contains(" ? "));
+    assert_eq!(GameBoard::from_text(&text)?.to_text(), text);
+
+    // Editors may strip trailing spaces
+    let stripped = text
+        .lines()
+        .map(|l| l.trim_end().to_string() + "\n")
+        .collect::<String>();
+    assert_eq!(GameBoard::from_text(&stripped)?.to_text(), text);
+
+    // A fully revealed board keeps its mines
+    gb.squares.iter_mut().for_each(|s| {
+        s.is_revealed = true;
+        s.marking = Marking::None;
+    });
+    let loaded = GameBoard::from_text(&gb.to_text())?;
+    assert_eq!(loaded.num_mines, 10);
+    assert_eq!(loaded.squares, gb.squares);
+
+    assert!(GameBoard::from_text(" # \n").is_err());
+    assert!(GameBoard::from_text("").is_err());
+
+    // Boards too large to play are refused before any squares are made
+    assert!(GameBoard::from_text(&" X ".repeat(MAX_DIMENSION as usize + 1)).is_err());
+    assert!(GameBoard::from_text(&" X \n".repeat(MAX_DIMENSION as usize + 1)).is_err());
+    assert!(GameBoard::from_text(&" X \n".repeat(MAX_DIMENSION as usize)).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_mbf() -> Result<(), Error> {
+    // 3x2 with mines at (0, 0) and (2, 1)
+    let bytes = [3, 2, 0, 2, 0, 0, 2, 1];
+    let gb = GameBoard::from_mbf(&bytes)?;
+    assert_eq!((gb.width, gb.height, gb.num_mines), (3, 2, 2));
+    assert!(gb.get_square(0, 0)?.is_mine());
+    assert!(gb.get_square(2, 1)?.is_mine());
+    assert_eq!(gb.get_square(1, 0)?.numeral, 2);
+    assert_eq!(gb.to_mbf()?, bytes);
+
+    assert!(GameBoard::from_mbf(&[3, 2, 0, 2, 0, 0]).is_err());
+    assert!(GameBoard::from_mbf(&[3, 2, 0, 1, 3, 0]).is_err());
+    assert!(GameBoard::from_mbf(&[3, 2, 0, 2, 0, 0, 0, 0]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_hex_neighborhood() -> Result<(), Error> {
+    let mut gb = GameBoard::new(4, 4);
+    gb.variant.neighborhood = Neighborhood::Hex;
+    let around = |gb: &GameBoard, x: u32, y: u32| {
+        let mut around = gb.around(x, y);
+        around.sort();
+        around
+    };
+
+    // Even rows touch the squares above and below to their left, odd rows those to
+    // their right
+    assert_eq!(
+        around(&gb, 1, 2),
+        [(0, 1), (0, 2), (0, 3), (1, 1), (1, 2), (1, 3), (2, 2)]
+    );
+    assert_eq!(
+        around(&gb, 1, 1),
+        [(0, 1), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+    );
+
+    gb.squares[0] = Square::default_mine();
+    gb.num_mines = 1;
+    gb.populate_numerals()?;
+    assert_eq!(gb.get_square(1, 0)?.numeral, 1);
+    assert_eq!(gb.get_square(0, 1)?.numeral, 1);
+    assert_eq!(gb.get_square(1, 1)?.numeral, 0);
+
+    // Opening the far corner cascades across the whole board
+    gb.play(3, 3, RevealType::Reveal)?;
+    assert!(gb.is_win_configuration());
+
+    // Only the six hexagons around the first click are kept clear
+    let mut gb = GameBoard::new(3, 3);
+    gb.variant.neighborhood = Neighborhood::Hex;
+    gb.populate_mines_around(2, Some(Coordinate { x: 1, y: 1 }))?;
+    assert!(gb.get_square(0, 0)?.is_mine());
+    assert!(gb.get_square(0, 2)?.is_mine());
+    let mut gb = GameBoard::new(3, 3);
+    gb.variant.neighborhood = Neighborhood::Hex;
+    assert!(gb
+        .populate_mines_around(3, Some(Coordinate { x: 1, y: 1 }))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_torus_topology() -> Result<(), Error> {
+    let mut gb = GameBoard::new(5, 4);
+    gb.variant.topology = Topology::Torus;
+
+    // Every square has all eight neighbors, reaching over the edges
+    iproduct!(0..5, 0..4).for_each(|(x, y)| assert_eq!(gb.around(x, y).len(), 9));
+    let mut corner = gb
+        .around(0, 0)
+        .into_iter()
+        .map(|(x, y)| gb.wrap(x, y).map(|c| (c.x, c.y)).unwrap())
+        .collect::<Vec<(u32, u32)>>();
+    corner.sort();
+    assert_eq!(
+        corner,
+        [
+            (0, 0),
+            (0, 1),
+            (0, 3),
+            (1, 0),
+            (1, 1),
+            (1, 3),
+            (4, 0),
+            (4, 1),
+            (4, 3)
+        ]
+    );
+    assert!(gb.is_near(&Coordinate { x: 0, y: 0 }, &Coordinate { x: 4, y: 3 }));
+    assert!(gb.distance(&Coordinate { x: 0, y: 0 }, &Coordinate { x: 4, y: 3 }) < 1.5);
+
+    // Mines are counted, and revealed, across the edges
+    gb.squares[3 * 5 + 4] = Square::default_mine();
+    gb.num_mines = 1;
+    gb.populate_numerals()?;
+    assert_eq!(gb.get_square(0, 0)?.numeral, 1);
+    assert_eq!(gb.get_square(0, 2)?.numeral, 1);
+    assert_eq!(gb.get_square(2, 1)?.numeral, 0);
+    gb.play(2, 1, RevealType::Reveal)?;
+    assert!(gb.is_win_configuration());
+    assert_eq!(gb.three_bv(), 1);
+
+    // A board narrower than the neighborhood counts each neighbor once
+    let mut narrow = GameBoard::new_with_layout(2, 3, &[true, false, false, false, false, false])?;
+    narrow.variant.topology = Topology::Torus;
+    narrow.populate_numerals()?;
+    assert_eq!(narrow.get_square(1, 1)?.numeral, 1);
+    assert_eq!(narrow.around(1, 1).len(), 6);
+
+    // The squares kept clear around the first click wrap too
+    let mut gb = GameBoard::new(4, 4);
+    gb.variant.topology = Topology::Torus;
+    gb.populate_mines_around(7, Some(Coordinate { x: 0, y: 0 }))?;
+    [(0, 0), (3, 3), (3, 0), (0, 3), (1, 3)]
+        .iter()
+        .try_for_each(|(x, y)| {
+            assert!(!gb.get_square(*x, *y)?.is_mine());
+            Ok(())
+        })?;
+    let mut gb = GameBoard::new(4, 4);
+    gb.variant.topology = Topology::Torus;
+    assert!(gb
+        .populate_mines_around(8, Some(Coordinate { x: 0, y: 0 }))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_variants() {
+    let torus = Variant::new(Neighborhood::Square, Topology::Torus);
+    assert_eq!(torus.key(), "torus");
+    assert_eq!(Variant::from_key("torus"), Some(torus));
+    assert_eq!(Variant::from_key("hex-torus"), None);
+    assert!(Variant::default().is_classic());
+
+    // Hexagonal boards can't wrap, and are kept bounded
+    assert_eq!(
+        Variant::new(Neighborhood::Hex, Topology::Torus).topology,
+        Topology::Bounded
+    );
+    assert_eq!(Variant::all().len(), 7);
+    assert_eq!(
+        Variant::new(Neighborhood::Knight, Topology::Torus).key(),
+        "knight-torus"
+    );
+}
+
+#[test]
+fn test_knight_and_orthogonal_neighborhoods() -> Result<(), Error> {
+    // A mine in the middle of the board
+    let mut layout = vec![false; 5 * 5];
+    layout[2 * 5 + 2] = true;
+
+    // Knight's move numerals count the mine from two squares along and one across,
+    // but not from beside it
+    let mut gb = GameBoard::new_with_layout(5, 5, &layout)?;
+    gb.variant.neighborhood = Neighborhood::Knight;
+    gb.populate_numerals()?;
+    assert_eq!(gb.get_square(1, 0)?.numeral, 1);
+    assert_eq!(gb.get_square(4, 3)?.numeral, 1);
+    assert_eq!(gb.get_square(1, 1)?.numeral, 0);
+    assert_eq!(gb.get_square(2, 1)?.numeral, 0);
+
+    // So an opening beside the mine cascades by knight's moves, reaching the whole
+    // board
+    gb.play(2, 1, RevealType::Reveal)?;
+    assert!(gb.get_square(0, 0)?.is_revealed);
+    assert!(gb.is_win_configuration());
+
+    // Chords reach by knight's moves too
+    let mut gb = GameBoard::new_with_layout(5, 5, &layout)?;
+    gb.variant.neighborhood = Neighborhood::Knight;
+    gb.populate_numerals()?;
+    gb.play(0, 1, RevealType::Reveal)?;
+    gb.play(2, 2, RevealType::Flag)?;
+    gb.play(0, 1, RevealType::Chord)?;
+    assert!(gb.get_square(1, 3)?.is_revealed);
+    assert!(gb.get_square(2, 0)?.is_revealed);
+
+    // Orthogonal numerals only count the four squares beside a square
+    let mut gb = GameBoard::new_with_layout(5, 5, &layout)?;
+    gb.variant.neighborhood = Neighborhood::Orthogonal;
+    gb.populate_numerals()?;
+    assert_eq!(gb.get_square(2, 1)?.numeral, 1);
+    assert_eq!(gb.get_square(1, 1)?.numeral, 0);
+    gb.play(0, 0, RevealType::Reveal)?;
+    assert!(gb.get_square(1, 1)?.is_revealed);
+    assert!(gb.is_win_configuration());
+
+    // The squares kept clear around the first click follow the neighborhood
+    let mut gb = GameBoard::new(3, 3);
+    gb.variant.neighborhood = Neighborhood::Orthogonal;
+    gb.populate_mines_around(4, Some(Coordinate { x: 1, y: 1 }))?;
+    [(0, 0), (2, 0), (0, 2), (2, 2)]
+        .iter()
+        .try_for_each(|(x, y)| {
+            assert!(gb.get_square(*x, *y)?.is_mine());
+            Ok(())
+        })
+}
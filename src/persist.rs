@@ -0,0 +1,247 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The key holding the schema version in every file we write
+const VERSION_KEY: &str = "version";
+
+const APP_DIR_NAME: &str = "minesofrust";
+
+/// Numbers the temporary files written by this process, so concurrent writes of
+/// the same file don't share one
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Settings, themes and skins. `~/.config/minesofrust` on Linux.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join(APP_DIR_NAME))
+}
+
+/// Leaderboards and other accumulated data. `~/.local/share/minesofrust`
+/// on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join(APP_DIR_NAME))
+}
+
+/// Where every file was kept by earlier versions of the game
+pub fn legacy_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".apoapsys"))
+}
+
+/// Returns the path of `file_name` in `dir`. If it doesn't exist there yet but
+/// `legacy_name` exists in the legacy directory, that file is copied over first.
+/// The legacy file is left in place.
+pub fn resolve_path(dir: Option<PathBuf>, file_name: &str, legacy_name: &str) -> Result<PathBuf> {
+    let path = dir
+        .ok_or(anyhow!("Unable to determine the platform directories"))?
+        .join(file_name);
+    if !path.exists() {
+        if let Some(legacy_path) = legacy_dir().map(|d| d.join(legacy_name)) {
+            if legacy_path.is_file() {
+                println!("Migrating {:?} to {:?}", legacy_path, path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::copy(&legacy_path, &path)?;
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Writes a file so that it's either entirely replaced or left untouched. The
+/// contents go to a temporary file beside it, which is then renamed over it. The
+/// temporary file is named for this process so other writers don't collide with
+/// it, and is removed if the write fails.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file_name = path
+        .file_name()
+        .ok_or(anyhow!("Invalid file path {:?}", path))?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = File::create(&tmp_path).and_then(|mut f| {
+        f.write_all(contents)?;
+        f.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    Ok(result?)
+}
+
+/// Upgrades a file's contents from one schema version to the next
+pub type Migration = fn(&mut toml::Table);
+
+/// A type saved to a TOML file with a schema version.
+///
+/// Files written before versioning was added have no version and are treated as
+/// version 1. When the schema changes, bump `VERSION` and add a migration from the
+/// previous version to `migrations()`.
+pub trait Versioned: Serialize + DeserializeOwned {
+    const VERSION: u32;
+
+    /// Migrations in order, where the first upgrades version 1 to version 2
+    fn migrations() -> Vec<Migration> {
+        vec![]
+    }
+}
+
+/// Parses versioned TOML, migrating older versions to the current schema
+pub fn from_versioned_str<T: Versioned>(s: &str) -> Result<T> {
+    let mut table: toml::Table = toml::from_str(s)?;
+    let version = match table.remove(VERSION_KEY) {
+        Some(v) => v
+            .as_integer()
+            .ok_or(anyhow!("Schema version is not an integer"))? as u32,
+        None => 1,
+    };
+
+    if version > T::VERSION {
+        return Err(anyhow!(
+            "Schema version {} is newer than supported version {}",
+            version,
+            T::VERSION
+        ));
+    }
+
+    T::migrations()
+        .iter()
+        .skip(version.max(1) as usize - 1)
+        .for_each(|m| m(&mut table));
+
+    Ok(table.try_into()?)
+}
+
+/// Serializes to TOML, stamped with the current schema version
+pub fn to_versioned_string<T: Versioned>(value: &T) -> Result<String> {
+    let mut table = toml::Table::try_from(value)?;
+    table.insert(
+        VERSION_KEY.to_string(),
+        toml::Value::Integer(T::VERSION as i64),
+    );
+    Ok(toml::to_string(&table)?)
+}
+
+/// Copies a file aside, with a timestamp, so it isn't lost when it's overwritten
+pub fn backup_file(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or(anyhow!("Invalid file path {:?}", path))?
+        .to_string_lossy();
+    let backup_path = path.with_file_name(format!(
+        "{}.{}.bak",
+        file_name,
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::copy(path, &backup_path)?;
+    Ok(backup_path)
+}
+
+/// Loads a versioned file. A file that can't be loaded is backed up before the
+/// error is returned, so the defaults the caller falls back to don't clobber it.
+pub fn load_versioned<T: Versioned>(path: &Path) -> Result<T> {
+    let t = std::fs::read_to_string(path)?;
+    match from_versioned_str(&t) {
+        Ok(v) => Ok(v),
+        Err(why) => {
+            match backup_file(path) {
+                Ok(b) => println!("Failed to load {:?}, backed up to {:?}: {}", path, b, why),
+                Err(e) => println!("Failed to load {:?} and failed to back it up: {}", path, e),
+            }
+            Err(why)
+        }
+    }
+}
+
+pub fn save_versioned<T: Versioned>(value: &T, path: &Path) -> Result<()> {
+    write_atomic(path, to_versioned_string(value)?.as_bytes())
+}
+
+#[derive(Debug, serde::Deserialize, Serialize, PartialEq)]
+#[cfg(test)]
+struct TestSchema {
+    name: String,
+    size: u32,
+}
+
+#[cfg(test)]
+impl Versioned for TestSchema {
+    const VERSION: u32 = 3;
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            // 1 -> 2: 'title' was renamed to 'name'
+            |t| {
+                if let Some(v) = t.remove("title") {
+                    t.insert("name".to_string(), v);
+                }
+            },
+            // 2 -> 3: 'size' was added
+            |t| {
+                t.insert("size".to_string(), toml::Value::Integer(9));
+            },
+        ]
+    }
+}
+
+#[test]
+fn test_versioned_migrations() -> Result<()> {
+    let expected = TestSchema {
+        name: "board".to_string(),
+        size: 9,
+    };
+
+    // Unversioned files are version 1
+    assert_eq!(
+        from_versioned_str::<TestSchema>("title = \"board\"")?,
+        expected
+    );
+    assert_eq!(
+        from_versioned_str::<TestSchema>("version = 2\nname = \"board\"")?,
+        expected
+    );
+
+    let s = to_versioned_string(&expected)?;
+    assert!(s.contains("version = 3"));
+    assert_eq!(from_versioned_str::<TestSchema>(&s)?, expected);
+
+    assert!(from_versioned_str::<TestSchema>("version = 4\nname = \"board\"\nsize = 1").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_write_atomic() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("minesofrust-persist-test-{}", std::process::id()));
+    let path = dir.join("nested/state.toml");
+
+    write_atomic(&path, b"first")?;
+    write_atomic(&path, b"second")?;
+    assert_eq!(std::fs::read_to_string(&path)?, "second");
+    assert_eq!(std::fs::read_dir(dir.join("nested"))?.count(), 1);
+
+    // A failed rename, here over a non-empty directory, leaves no temporary file
+    std::fs::create_dir_all(dir.join("blocked/inside"))?;
+    assert!(write_atomic(&dir.join("blocked"), b"third").is_err());
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 2);
+
+    let backup = backup_file(&path)?;
+    assert_eq!(std::fs::read_to_string(backup)?, "second");
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
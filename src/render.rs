@@ -0,0 +1,538 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use egui::Color32;
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Rgba, RgbaImage};
+use imageproc::drawing::{
+    draw_filled_rect_mut, draw_hollow_polygon_mut, draw_hollow_rect_mut, draw_polygon_mut,
+};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+
+use crate::enums::GameState;
+use crate::hexgrid::{in_hexagon, HexGrid};
+use crate::minesweeper::{Coordinate, GameBoard, Neighborhood, Topology};
+use crate::persist::write_atomic;
+use crate::skin::SkinTile;
+use crate::textures::{is_flag_cloth, split_mask};
+use crate::theme::Palette;
+use crate::tile::Tile;
+
+pub const DEFAULT_SQUARE_SIZE: u32 = 32;
+pub const MIN_SQUARE_SIZE: u32 = 8;
+pub const MAX_SQUARE_SIZE: u32 = 256;
+
+/// The largest width or height of a rendered image, in pixels
+pub const MAX_IMAGE_SIZE: u32 = 8192;
+
+/// How a board is drawn without a window
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// The width and height of a square, in pixels
+    pub square_size: u32,
+    pub palette: Palette,
+
+    /// Show where every mine is, whether or not the game was lost
+    pub show_mines: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            square_size: DEFAULT_SQUARE_SIZE,
+            palette: Palette::light(),
+            show_mines: false,
+        }
+    }
+}
+
+/// Draws boards into images from the built-in tile images, the same way the game
+/// draws them on screen. Each kind of square is composed once, up front, so drawing
+/// many boards, such as the frames of a replay, is cheap.
+pub struct BoardRenderer {
+    options: RenderOptions,
+    squares: HashMap<Tile, RgbaImage>,
+
+    /// The tiles of hexagonal boards, drawn over a hexagon filled by `fill_color`
+    hexagons: HashMap<Tile, RgbaImage>,
+}
+
+fn rgba(color: Color32) -> Rgba<u8> {
+    Rgba(color.to_srgba_unmultiplied())
+}
+
+/// Multiplies each pixel by the tint, as egui does when drawing a tinted image
+fn tinted(image: &RgbaImage, tint: Color32) -> RgbaImage {
+    let tint = tint.to_srgba_unmultiplied();
+    let mut image = image.clone();
+    image.pixels_mut().for_each(|p| {
+        (0..4).for_each(|c| p[c] = (p[c] as u32 * tint[c] as u32 / 255) as u8);
+    });
+    image
+}
+
+fn all_tiles() -> Vec<Tile> {
+    let mut tiles = vec![
+        Tile::Unrevealed,
+        Tile::Pressed,
+        Tile::Flagged,
+        Tile::Questioned,
+        Tile::Blank,
+        Tile::Mine,
+        Tile::Detonated,
+        Tile::Blasted,
+        Tile::FlagCorrect,
+        Tile::MisFlagged,
+    ];
+    tiles.extend((1..=8).map(Tile::Numeral));
+    tiles
+}
+
+/// The state of the game a board shows
+fn game_state(board: &GameBoard) -> GameState {
+    if board.is_loss_configuration() {
+        GameState::EndedLoss
+    } else if board.is_win_configuration() {
+        GameState::EndedWin
+    } else {
+        GameState::Playing
+    }
+}
+
+impl BoardRenderer {
+    pub fn new(options: RenderOptions) -> Result<Self> {
+        if !(MIN_SQUARE_SIZE..=MAX_SQUARE_SIZE).contains(&options.square_size) {
+            return Err(anyhow!(
+                "Squares must be between {} and {} pixels",
+                MIN_SQUARE_SIZE,
+                MAX_SQUARE_SIZE
+            ));
+        }
+        let mut renderer = BoardRenderer {
+            options,
+            squares: HashMap::new(),
+            hexagons: HashMap::new(),
+        };
+        renderer.squares = all_tiles()
+            .into_iter()
+            .map(|t| (t, renderer.compose(t, false)))
+            .collect();
+        renderer.hexagons = all_tiles()
+            .into_iter()
+            .map(|t| (t, renderer.compose(t, true)))
+            .collect();
+        Ok(renderer)
+    }
+
+    /// A built-in tile image, scaled to the square size
+    fn tile_image(&self, tile: SkinTile) -> RgbaImage {
+        let size = self.options.square_size;
+        let image = image::load_from_memory(tile.builtin())
+            .expect("Failed to decode built-in image")
+            .into_rgba8();
+        imageops::resize(&image, size, size, FilterType::Triangle)
+    }
+
+    /// Draws a tile over a square. As on screen, the tint applies to the images the
+    /// game colors by theme: the square backgrounds, numerals and flag cloth.
+    fn paint(&self, square: &mut RgbaImage, tile: SkinTile, tint: Color32) {
+        let image = self.tile_image(tile);
+        match tile {
+            SkinTile::Numeral(_) => {
+                imageops::overlay(square, &tinted(&split_mask(&image, |_| true).0, tint), 0, 0)
+            }
+            SkinTile::Flag => {
+                let (cloth, pole) = split_mask(&image, is_flag_cloth);
+                imageops::overlay(square, &pole, 0, 0);
+                imageops::overlay(square, &tinted(&cloth, tint), 0, 0);
+            }
+            SkinTile::Unrevealed | SkinTile::Blank => {
+                imageops::overlay(square, &tinted(&image, tint), 0, 0)
+            }
+            _ => imageops::overlay(square, &image, 0, 0),
+        }
+    }
+
+    /// The color a square is filled with before anything is drawn on it
+    fn fill_color(&self, tile: Tile) -> Color32 {
+        let palette = &self.options.palette;
+        match tile {
+            Tile::Unrevealed | Tile::Flagged | Tile::Questioned | Tile::FlagCorrect => {
+                palette.unrevealed
+            }
+            Tile::MisFlagged => palette.misflagged,
+            Tile::Detonated => palette.detonated,
+            Tile::Blasted | Tile::Pressed | Tile::Blank | Tile::Numeral(_) | Tile::Mine => {
+                palette.revealed
+            }
+        }
+    }
+
+    /// Composes a square the way the game's board draws it: a background, then
+    /// anything shown on the square. A hexagon's tile is left unfilled and without
+    /// the bevelled square backgrounds, and is cut to the hexagon's shape.
+    fn compose(&self, tile: Tile, hexagon: bool) -> RgbaImage {
+        let palette = &self.options.palette;
+        let size = self.options.square_size;
+        let mut square = if hexagon {
+            RgbaImage::new(size, size)
+        } else {
+            RgbaImage::from_pixel(size, size, rgba(self.fill_color(tile)))
+        };
+
+        match tile {
+            Tile::Unrevealed | Tile::Flagged | Tile::Questioned | Tile::FlagCorrect if !hexagon => {
+                self.paint(&mut square, SkinTile::Unrevealed, palette.unrevealed)
+            }
+            Tile::Detonated => self.paint(&mut square, SkinTile::Blast, palette.detonated),
+            Tile::Blasted => {
+                let blast = tinted(
+                    &self.tile_image(SkinTile::Blast),
+                    Color32::from_white_alpha(128),
+                );
+                imageops::overlay(&mut square, &blast, 0, 0);
+            }
+            Tile::Pressed | Tile::Blank | Tile::Numeral(_) | Tile::Mine if !hexagon => {
+                self.paint(&mut square, SkinTile::Blank, palette.revealed)
+            }
+            _ => {}
+        };
+
+        match tile {
+            Tile::Flagged => self.paint(&mut square, SkinTile::Flag, palette.flag),
+            Tile::Questioned => self.paint(&mut square, SkinTile::Question, Color32::WHITE),
+            Tile::FlagCorrect => {
+                self.paint(&mut square, SkinTile::Flag, palette.flag);
+                self.paint(&mut square, SkinTile::FlagCorrect, Color32::WHITE);
+            }
+            Tile::MisFlagged => {
+                self.paint(&mut square, SkinTile::Flag, palette.flag);
+                self.paint(&mut square, SkinTile::MisFlag, Color32::WHITE);
+            }
+            Tile::Mine | Tile::Detonated | Tile::Blasted => {
+                self.paint(&mut square, SkinTile::Mine, Color32::WHITE)
+            }
+            Tile::Numeral(n) => {
+                let color = palette.numerals[(n as usize - 1).min(7)];
+                self.paint(&mut square, SkinTile::Numeral(n), color);
+            }
+            Tile::Unrevealed | Tile::Pressed | Tile::Blank => {}
+        };
+
+        if hexagon {
+            let half = size as f32 / 2.0;
+            square.enumerate_pixels_mut().for_each(|(x, y, p)| {
+                let offset = egui::vec2(x as f32 + 0.5 - half, y as f32 + 0.5 - half);
+                if !in_hexagon(size as f32, offset) {
+                    p[3] = 0;
+                }
+            });
+        }
+        square
+    }
+
+    /// The size of the image of a board: its squares and the border around them
+    pub fn image_size(&self, board: &GameBoard) -> (u32, u32) {
+        image_size(board, self.options.square_size)
+    }
+
+    fn hex_grid(&self, board: &GameBoard) -> HexGrid {
+        hex_grid(board, self.options.square_size)
+    }
+
+    /// Where the centre of a square is drawn
+    pub fn center(&self, board: &GameBoard, coord: &Coordinate) -> (f32, f32) {
+        match board.variant.neighborhood {
+            Neighborhood::Square | Neighborhood::Knight | Neighborhood::Orthogonal => {
+                let size = self.options.square_size as f32;
+                ((coord.x as f32 + 0.5) * size, (coord.y as f32 + 0.5) * size)
+            }
+            Neighborhood::Hex => {
+                let c = self.hex_grid(board).center(coord);
+                (c.x, c.y)
+            }
+        }
+    }
+
+    /// How a square is drawn. Any revealed mine is drawn as having detonated.
+    fn tile_for(&self, board: &GameBoard, state: &GameState, idx: usize) -> Tile {
+        let sqr = &board.squares[idx];
+        let is_detonated = sqr.is_mine() && sqr.is_revealed;
+        match Tile::for_square(sqr, state, is_detonated, false) {
+            Tile::Unrevealed | Tile::Questioned if self.options.show_mines && sqr.is_mine() => {
+                Tile::Mine
+            }
+            tile => tile,
+        }
+    }
+
+    /// Draws the board as it stands. Whether the game is in progress, lost or won is
+    /// read from the board, and any revealed mine is drawn as having detonated.
+    pub fn render(&self, board: &GameBoard) -> Result<RgbaImage> {
+        let (width, height) = self.image_size(board);
+        if width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+            return Err(anyhow!(
+                "The image would be larger than {0}x{0} pixels",
+                MAX_IMAGE_SIZE
+            ));
+        }
+
+        let state = game_state(board);
+        let size = self.options.square_size;
+        let border = rgba(self.options.palette.border);
+        let mut image = RgbaImage::from_pixel(width, height, border);
+
+        match board.variant.neighborhood {
+            Neighborhood::Square | Neighborhood::Knight | Neighborhood::Orthogonal => {
+                (0..board.squares.len()).for_each(|idx| {
+                    let tile = self.tile_for(board, &state, idx);
+                    let x = (idx as u32 % board.width) * size;
+                    let y = (idx as u32 / board.width) * size;
+                    imageops::replace(&mut image, &self.squares[&tile], x as i64, y as i64);
+                    draw_hollow_rect_mut(
+                        &mut image,
+                        Rect::at(x as i32, y as i32).of_size(size + 1, size + 1),
+                        border,
+                    );
+                })
+            }
+            Neighborhood::Hex => {
+                let grid = self.hex_grid(board);
+                (0..board.squares.len()).for_each(|idx| {
+                    let tile = self.tile_for(board, &state, idx);
+                    let coord = Coordinate {
+                        x: idx as u32 % board.width,
+                        y: idx as u32 / board.width,
+                    };
+                    let corners = grid.corners(&coord);
+                    draw_polygon_mut(
+                        &mut image,
+                        &corners
+                            .iter()
+                            .map(|p| Point::new(p.x.round() as i32, p.y.round() as i32))
+                            .collect::<Vec<Point<i32>>>(),
+                        rgba(self.fill_color(tile)),
+                    );
+                    let r = grid.tile_rect(&coord);
+                    imageops::overlay(
+                        &mut image,
+                        &self.hexagons[&tile],
+                        r.min.x.round() as i64,
+                        r.min.y.round() as i64,
+                    );
+                    draw_hollow_polygon_mut(
+                        &mut image,
+                        &corners
+                            .iter()
+                            .map(|p| Point::new(p.x, p.y))
+                            .collect::<Vec<Point<f32>>>(),
+                        border,
+                    );
+                });
+            }
+        }
+        wrap_marks(board, size as f32).iter().for_each(|r| {
+            draw_filled_rect_mut(
+                &mut image,
+                Rect::at(r.min.x.round() as i32, r.min.y.round() as i32)
+                    .of_size(r.width().round() as u32, r.height().round() as u32),
+                rgba(self.options.palette.flag),
+            )
+        });
+        Ok(image)
+    }
+}
+
+/// Where the edges of a board that wraps around are marked, relative to the top
+/// left of the board: a dash along the edge of each square on the border, showing
+/// it carries on at the other side. None for a bounded board.
+pub fn wrap_marks(board: &GameBoard, square_size: f32) -> Vec<egui::Rect> {
+    if board.variant.topology != Topology::Torus {
+        return vec![];
+    }
+    let thickness = (square_size / 12.0).round().max(2.0);
+    let (width, height) = (
+        board.width as f32 * square_size,
+        board.height as f32 * square_size,
+    );
+    let dash = |along: f32, across: f32, horizontal: bool| {
+        let start = along * square_size + square_size / 4.0;
+        let (min, size) = if horizontal {
+            (
+                egui::pos2(start, across),
+                egui::vec2(square_size / 2.0, thickness),
+            )
+        } else {
+            (
+                egui::pos2(across, start),
+                egui::vec2(thickness, square_size / 2.0),
+            )
+        };
+        egui::Rect::from_min_size(min, size)
+    };
+    (0..board.width)
+        .flat_map(|x| {
+            [
+                dash(x as f32, 0.0, true),
+                dash(x as f32, height + 1.0 - thickness, true),
+            ]
+        })
+        .chain((0..board.height).flat_map(|y| {
+            [
+                dash(y as f32, 0.0, false),
+                dash(y as f32, width + 1.0 - thickness, false),
+            ]
+        }))
+        .collect()
+}
+
+fn hex_grid(board: &GameBoard, square_size: u32) -> HexGrid {
+    HexGrid {
+        origin: egui::Pos2::ZERO,
+        size: square_size as f32,
+        width: board.width,
+        height: board.height,
+    }
+}
+
+/// The size of the image of a board with squares of a size, including the border
+/// around them
+pub fn image_size(board: &GameBoard, square_size: u32) -> (u32, u32) {
+    match board.variant.neighborhood {
+        Neighborhood::Square | Neighborhood::Knight | Neighborhood::Orthogonal => (
+            board.width * square_size + 1,
+            board.height * square_size + 1,
+        ),
+        Neighborhood::Hex => {
+            let size = hex_grid(board, square_size).board_size();
+            (size.x.ceil() as u32 + 1, size.y.ceil() as u32 + 1)
+        }
+    }
+}
+
+/// Draws a board to an image, such as for sharing a result without a window
+pub fn render_board(board: &GameBoard, options: RenderOptions) -> Result<RgbaImage> {
+    BoardRenderer::new(options)?.render(board)
+}
+
+/// Draws a board to a PNG file
+pub fn save_png(board: &GameBoard, path: &Path, options: RenderOptions) -> Result<()> {
+    let image = render_board(board, options)?;
+    let mut bytes = vec![];
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    write_atomic(path, &bytes)
+}
+
+#[test]
+fn test_render_board() -> Result<()> {
+    use crate::minesweeper::RevealType;
+
+    let mut board = crate::replay::small_test_board();
+    let options = RenderOptions {
+        square_size: 16,
+        ..Default::default()
+    };
+    let renderer = BoardRenderer::new(options.clone())?;
+
+    // Which tile each square was drawn with, judged by its centre
+    let drawn = |renderer: &BoardRenderer, image: &RgbaImage, x: u32, y: u32, tile: Tile| {
+        image.get_pixel(x * 16 + 8, y * 16 + 8) == renderer.squares[&tile].get_pixel(8, 8)
+    };
+
+    let image = renderer.render(&board)?;
+    assert_eq!(image.dimensions(), (65, 49));
+    assert_eq!(*image.get_pixel(16, 8), rgba(options.palette.border));
+    assert!(drawn(&renderer, &image, 3, 0, Tile::Unrevealed));
+
+    // In progress: mines stay hidden unless asked for
+    board.play(0, 0, RevealType::Reveal).unwrap();
+    board.play(3, 0, RevealType::Flag).unwrap();
+    let image = renderer.render(&board)?;
+    assert!(drawn(&renderer, &image, 0, 0, Tile::Blank));
+    assert!(drawn(&renderer, &image, 2, 1, Tile::Numeral(2)));
+    assert!(drawn(&renderer, &image, 3, 0, Tile::Flagged));
+    assert!(drawn(&renderer, &image, 3, 2, Tile::Unrevealed));
+
+    let shown = BoardRenderer::new(RenderOptions {
+        show_mines: true,
+        ..options.clone()
+    })?;
+    let image = shown.render(&board)?;
+    assert!(drawn(&shown, &image, 3, 2, Tile::Mine));
+    assert!(drawn(&shown, &image, 3, 0, Tile::Flagged));
+
+    // Lost: the revealed mine detonated, and the flag is confirmed
+    board.play(3, 2, RevealType::Reveal).unwrap();
+    let image = renderer.render(&board)?;
+    assert!(drawn(&renderer, &image, 3, 2, Tile::Detonated));
+    assert!(drawn(&renderer, &image, 3, 0, Tile::FlagCorrect));
+
+    // Too large for an image, or for a square
+    assert!(renderer.render(&GameBoard::new(600, 10)).is_err());
+    assert!(BoardRenderer::new(RenderOptions {
+        square_size: 1000,
+        ..options
+    })
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_render_hex_board() -> Result<()> {
+    use crate::minesweeper::RevealType;
+
+    let mut board = crate::replay::small_test_board();
+    board.variant.neighborhood = Neighborhood::Hex;
+    board.populate_numerals().unwrap();
+    let options = RenderOptions {
+        square_size: 16,
+        ..Default::default()
+    };
+    let renderer = BoardRenderer::new(options.clone())?;
+
+    // Half a square wider than a board of squares, and shorter since the rows
+    // interlock
+    let image = renderer.render(&board)?;
+    assert_eq!(image.dimensions(), (73, 48));
+    assert_eq!(*image.get_pixel(0, 0), rgba(options.palette.border));
+    let center = |x: u32, y: u32| {
+        let (cx, cy) = renderer.center(&board, &Coordinate { x, y });
+        (cx as u32, cy as u32)
+    };
+    let (hidden, opened) = (center(1, 1), center(0, 0));
+    assert_eq!(
+        *image.get_pixel(hidden.0, hidden.1),
+        rgba(options.palette.unrevealed)
+    );
+
+    board.play(0, 0, RevealType::Reveal).unwrap();
+    let image = renderer.render(&board)?;
+    let (x, y) = opened;
+    assert_eq!(*image.get_pixel(x, y), rgba(options.palette.revealed));
+    Ok(())
+}
+
+#[test]
+fn test_render_torus_board() -> Result<()> {
+    let mut board = GameBoard::new_with_layout(4, 3, &[false; 12]).unwrap();
+    let options = RenderOptions {
+        square_size: 16,
+        ..Default::default()
+    };
+    let bounded = render_board(&board, options.clone())?;
+
+    // The middle of each edge square is marked, and the corners between them aren't
+    board.variant.topology = Topology::Torus;
+    let image = render_board(&board, options.clone())?;
+    assert_eq!(image.dimensions(), bounded.dimensions());
+    let flag = rgba(options.palette.flag);
+    [(8, 0), (8, 48), (0, 40), (64, 24)]
+        .iter()
+        .for_each(|(x, y)| assert_eq!(*image.get_pixel(*x, *y), flag));
+    assert_eq!(*image.get_pixel(16, 0), rgba(options.palette.border));
+    assert_eq!(wrap_marks(&board, 16.0).len(), 2 * (4 + 3));
+    Ok(())
+}
@@ -0,0 +1,663 @@
+use anyhow::{anyhow, Result};
+
+use crate::boardcode::{self, BoardCode, BoardLayout};
+use crate::minesweeper::{
+    Coordinate, GameBoard, Neighborhood, PlayResult, RevealType, Topology, Variant,
+};
+
+/// Prefix of the replay code, so the format can change without misreading old codes
+const CODE_VERSION: &str = "r1";
+
+/// How far the claimed time of a game may be from the time of its final move, in
+/// seconds. The timer stops on the frame the win is detected, a moment after the
+/// final move.
+const TIME_TOLERANCE: f64 = 0.1;
+
+/// How late the first move of a game may be, in seconds. The timer starts with the
+/// first reveal, and flags placed before it are recorded at the start.
+const START_TOLERANCE: f64 = 0.1;
+
+/// The shortest time between two moves once the game has started, in seconds.
+/// Moves made on the same frame are closer, but no one clicks that quickly.
+const MIN_MOVE_GAP: f64 = 0.01;
+
+/// The fastest a replay may clear the board's 3BV, beyond the first click,
+/// which is free. Well above the best players.
+const MAX_THREE_BV_PER_SECOND: f64 = 12.0;
+
+/// The fastest a replay may make moves once the game has started
+const MAX_CLICKS_PER_SECOND: f64 = 20.0;
+
+/// The latest a move may be made, in seconds from the start of the game. Far
+/// longer than any game, but small enough that times can be scaled and added up
+/// without overflowing.
+pub const MAX_MOVE_TIME: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+
+/// The largest board a replay may describe, so a doctored replay can't exhaust
+/// memory
+const MAX_SQUARES: u32 = 1_000_000;
+
+/// A single play, timed from the start of the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayMove {
+    /// Seconds since the timer started
+    pub time: f64,
+    pub coord: Coordinate,
+    pub play_type: RevealType,
+}
+
+/// Everything needed to play a game again: the board, which is determined by its
+/// seed and first click, and every move made on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub width: u32,
+    pub height: u32,
+    pub num_mines: u32,
+    pub seed: u64,
+    pub first_click: Coordinate,
+
+    /// The mines, in row order, of a board the seed can't reproduce, such as one
+    /// opened from a file or recorded by another game
+    pub layout: Option<Vec<bool>>,
+
+    pub use_question_marks: bool,
+    pub question_blocks_reveal: bool,
+    pub variant: Variant,
+    pub moves: Vec<ReplayMove>,
+}
+
+fn play_type_code(play_type: &RevealType) -> char {
+    match play_type {
+        RevealType::Reveal => 'R',
+        RevealType::RevealChord => 'A',
+        RevealType::Chord => 'C',
+        RevealType::Flag => 'F',
+    }
+}
+
+fn play_type_from_code(code: &str) -> Result<RevealType> {
+    match code {
+        "R" => Ok(RevealType::Reveal),
+        "A" => Ok(RevealType::RevealChord),
+        "C" => Ok(RevealType::Chord),
+        "F" => Ok(RevealType::Flag),
+        _ => Err(anyhow!("Invalid play type '{}'", code)),
+    }
+}
+
+/// The letter of a neighborhood in the rules of a replay code. The classic game
+/// has none.
+fn neighborhood_rule(neighborhood: Neighborhood) -> Option<char> {
+    match neighborhood {
+        Neighborhood::Square => None,
+        Neighborhood::Hex => Some('h'),
+        Neighborhood::Knight => Some('k'),
+        Neighborhood::Orthogonal => Some('o'),
+    }
+}
+
+/// The rules field of a replay code, a letter for each rule in use
+fn rules_code(use_question_marks: bool, question_blocks_reveal: bool, variant: Variant) -> String {
+    [
+        (use_question_marks, Some('q')),
+        (use_question_marks && question_blocks_reveal, Some('b')),
+        (true, neighborhood_rule(variant.neighborhood)),
+        (variant.topology == Topology::Torus, Some('w')),
+    ]
+    .iter()
+    .filter(|(on, _)| *on)
+    .filter_map(|(_, c)| *c)
+    .collect()
+}
+
+/// Whether a play revealed a mine
+fn detonates(result: &PlayResult) -> bool {
+    match result {
+        PlayResult::Explosion(_) => true,
+        PlayResult::CascadedReveal(results) => results.iter().any(detonates),
+        _ => false,
+    }
+}
+
+impl Replay {
+    /// The replay of a game on `board`. None if no mines have been placed yet.
+    pub fn new(board: &GameBoard, moves: Vec<ReplayMove>) -> Option<Self> {
+        let (seed, layout) = match BoardCode::for_board(board).layout {
+            BoardLayout::Seed(seed) => (seed, None),
+            BoardLayout::Mines(mines) => (0, Some(mines)),
+        };
+        board.first_click.as_ref().map(|first_click| Replay {
+            width: board.width,
+            height: board.height,
+            num_mines: board.num_mines,
+            seed,
+            first_click: first_click.clone(),
+            layout,
+            use_question_marks: board.use_question_marks,
+            question_blocks_reveal: board.question_blocks_reveal,
+            variant: board.variant,
+            moves,
+        })
+    }
+
+    /// The board as it was before the first move
+    pub fn board(&self) -> Result<GameBoard> {
+        match self.width.checked_mul(self.height) {
+            Some(n) if n > 0 && n <= MAX_SQUARES => {
+                if self.num_mines >= n {
+                    return Err(anyhow!(
+                        "Too many mines for a {}x{} board",
+                        self.width,
+                        self.height
+                    ));
+                }
+            }
+            _ => return Err(anyhow!("Invalid board size")),
+        }
+        if self.first_click.x >= self.width || self.first_click.y >= self.height {
+            return Err(anyhow!("First click is off the board"));
+        }
+        let mut board = match &self.layout {
+            Some(mines) => {
+                let mut board = GameBoard::new_with_layout(self.width, self.height, mines)
+                    .map_err(|e| anyhow!("Invalid board: {:?}", e))?;
+                if board.num_mines != self.num_mines {
+                    return Err(anyhow!("The layout doesn't have {} mines", self.num_mines));
+                }
+                board.variant = self.variant;
+                board
+                    .populate_numerals()
+                    .map_err(|e| anyhow!("Invalid board: {:?}", e))?;
+                board
+            }
+            None => {
+                let mut board = GameBoard::new(self.width, self.height);
+                board.seed = self.seed;
+                board.variant = self.variant;
+                board
+                    .populate_mines_around(self.num_mines, Some(self.first_click.clone()))
+                    .map_err(|e| anyhow!("Invalid board: {:?}", e))?;
+                board
+                    .populate_numerals()
+                    .map_err(|e| anyhow!("Invalid board: {:?}", e))?;
+                board
+            }
+        };
+        board.first_click = Some(self.first_click.clone());
+        board.use_question_marks = self.use_question_marks;
+        board.question_blocks_reveal = self.question_blocks_reveal;
+        Ok(board)
+    }
+
+    /// Plays the moves again up to the end of the game, whether it was won, lost or
+    /// abandoned, and measures how it was played
+    pub fn analyse(&self) -> Result<ReplayStats> {
+        self.play_out().map(|(_, stats)| stats)
+    }
+
+    /// Plays the moves again up to the end of the game, giving the board as it was
+    /// left along with the measures of the game
+    pub fn play_out(&self) -> Result<(GameBoard, ReplayStats)> {
+        let mut board = self.board()?;
+        let mut stats = ReplayStats {
+            outcome: ReplayOutcome::Unfinished,
+            time: 0.0,
+            three_bv: board.three_bv(),
+            clicks: 0,
+            flags_used: 0,
+        };
+
+        for m in self.moves.iter() {
+            let result = board
+                .play(m.coord.x, m.coord.y, m.play_type.clone())
+                .map_err(|e| anyhow!("Move {} is invalid: {:?}", stats.clicks + 1, e))?;
+            stats.time = m.time;
+            stats.clicks += 1;
+            if m.play_type == RevealType::Flag {
+                stats.flags_used += 1;
+            }
+            if detonates(&result) {
+                stats.outcome = ReplayOutcome::Lost;
+                break;
+            } else if board.is_win_configuration() {
+                stats.outcome = ReplayOutcome::Won;
+                break;
+            }
+        }
+        Ok((board, stats))
+    }
+
+    /// Plays the moves again, confirming that they win the game and that the final
+    /// move was made at the claimed time.
+    ///
+    /// The move times are also checked to be humanly possible, but a replay is
+    /// only checked for consistency: one written by hand can still pass.
+    pub fn verify(&self, claimed_time: f64) -> Result<()> {
+        let mut board = self.board()?;
+        let mut last_time = 0.0;
+        let mut timed_moves = 0;
+
+        for (i, m) in self.moves.iter().enumerate() {
+            if !m.time.is_finite() || m.time < last_time {
+                return Err(anyhow!("Move {} is out of order", i + 1));
+            }
+            if i == 0 && m.time > START_TOLERANCE {
+                return Err(anyhow!("The first move isn't at the start of the game"));
+            }
+            if board.num_revealed() > 0 {
+                if m.time - last_time < MIN_MOVE_GAP {
+                    return Err(anyhow!("Move {} is too soon after the last", i + 1));
+                }
+                timed_moves += 1;
+            }
+            if board.is_win_configuration() {
+                return Err(anyhow!("Moves continue after the board is cleared"));
+            }
+            let result = board
+                .play(m.coord.x, m.coord.y, m.play_type.clone())
+                .map_err(|e| anyhow!("Move {} is invalid: {:?}", i + 1, e))?;
+            if detonates(&result) {
+                return Err(anyhow!("Move {} detonates a mine", i + 1));
+            }
+            last_time = m.time;
+        }
+
+        if !board.is_win_configuration() {
+            Err(anyhow!("The moves don't clear the board"))
+        } else if board.three_bv().saturating_sub(1) as f64 > last_time * MAX_THREE_BV_PER_SECOND {
+            Err(anyhow!("The board was cleared implausibly quickly"))
+        } else if timed_moves as f64 > last_time * MAX_CLICKS_PER_SECOND {
+            Err(anyhow!("The moves were made implausibly quickly"))
+        } else if (claimed_time - last_time).abs() > TIME_TOLERANCE {
+            Err(anyhow!(
+                "Claimed time {:.3} doesn't match the replay's {:.3}",
+                claimed_time,
+                last_time
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The number of flag moves
+    pub fn flags_used(&self) -> u32 {
+        self.moves
+            .iter()
+            .filter(|m| m.play_type == RevealType::Flag)
+            .count() as u32
+    }
+
+    /// A compact text form of the replay, such as
+    /// `r1:9x9x10:1f2e:4,4::0,4,4,R;1520,0,0,F`. Move times are in milliseconds. A
+    /// board with an explicit layout has `m` and its mines as hex in place of the
+    /// seed, as in a board code. The rules are a letter for each one in use: `q` for
+    /// question marks, `b` for question marks blocking reveals, `h`, `k` or `o` for
+    /// a hexagonal, knight's move or orthogonal board, and `w` for a board that
+    /// wraps around its edges.
+    pub fn to_code(&self) -> String {
+        let rules = rules_code(
+            self.use_question_marks,
+            self.question_blocks_reveal,
+            self.variant,
+        );
+        let moves = self
+            .moves
+            .iter()
+            .map(|m| {
+                format!(
+                    "{},{},{},{}",
+                    (m.time * 1000.0).round() as u64,
+                    m.coord.x,
+                    m.coord.y,
+                    play_type_code(&m.play_type)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(";");
+        let board = match &self.layout {
+            Some(mines) => format!("m{}", boardcode::mines_to_hex(mines)),
+            None => format!("{:x}", self.seed),
+        };
+        format!(
+            "{}:{}x{}x{}:{}:{},{}:{}:{}",
+            CODE_VERSION,
+            self.width,
+            self.height,
+            self.num_mines,
+            board,
+            self.first_click.x,
+            self.first_click.y,
+            rules,
+            moves
+        )
+    }
+
+    pub fn from_code(code: &str) -> Result<Self> {
+        let parts = code.trim().split(':').collect::<Vec<&str>>();
+        let [version, size, seed, first_click, rules, moves] = parts.as_slice() else {
+            return Err(anyhow!("Invalid replay code"));
+        };
+        if *version != CODE_VERSION {
+            return Err(anyhow!("Unsupported replay version '{}'", version));
+        }
+
+        let size = size
+            .split('x')
+            .map(|v| v.parse::<u32>())
+            .collect::<Result<Vec<u32>, _>>()?;
+        let [width, height, num_mines] = size.as_slice() else {
+            return Err(anyhow!("Invalid board size"));
+        };
+        let squares = match width.checked_mul(*height) {
+            Some(n) if n > 0 && n <= MAX_SQUARES => n,
+            _ => return Err(anyhow!("Invalid board size")),
+        };
+        if *num_mines >= squares {
+            return Err(anyhow!("Too many mines for a {}x{} board", width, height));
+        }
+        let (seed, layout) = match seed.strip_prefix('m') {
+            Some(hex) => (0, Some(boardcode::hex_to_mines(hex, squares as usize)?)),
+            None => (u64::from_str_radix(seed, 16)?, None),
+        };
+
+        // Each combination of rules has exactly one code
+        let use_question_marks = rules.contains('q');
+        let question_blocks_reveal = rules.contains('b');
+        let neighborhood = Neighborhood::all()
+            .into_iter()
+            .find(|n| neighborhood_rule(*n).is_some_and(|c| rules.contains(c)))
+            .unwrap_or(Neighborhood::Square);
+        let topology = if rules.contains('w') {
+            Topology::Torus
+        } else {
+            Topology::Bounded
+        };
+        let variant = Variant {
+            neighborhood,
+            topology,
+        };
+        if Variant::new(neighborhood, topology) != variant
+            || rules_code(use_question_marks, question_blocks_reveal, variant) != *rules
+        {
+            return Err(anyhow!("Invalid rules '{}'", rules));
+        }
+
+        let moves = moves
+            .split(';')
+            .filter(|m| !m.is_empty())
+            .map(|m| match m.split(',').collect::<Vec<&str>>().as_slice() {
+                [ms, x, y, play_type] => Ok(ReplayMove {
+                    time: ms.parse::<u64>()? as f64 / 1000.0,
+                    coord: Coordinate {
+                        x: x.parse()?,
+                        y: y.parse()?,
+                    },
+                    play_type: play_type_from_code(play_type)?,
+                }),
+                _ => Err(anyhow!("Invalid move '{}'", m)),
+            })
+            .collect::<Result<Vec<ReplayMove>>>()?;
+
+        Ok(Replay {
+            width: *width,
+            height: *height,
+            num_mines: *num_mines,
+            seed,
+            first_click: parse_coordinate(first_click)?,
+            layout,
+            use_question_marks,
+            question_blocks_reveal,
+            variant,
+            moves,
+        })
+    }
+}
+
+/// How a replayed game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayOutcome {
+    Won,
+    Lost,
+    Unfinished,
+}
+
+/// The measures of a replayed game, as used by the leaderboard rankings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayStats {
+    pub outcome: ReplayOutcome,
+
+    /// Seconds to the final move
+    pub time: f64,
+    pub three_bv: u32,
+    pub clicks: u32,
+    pub flags_used: u32,
+}
+
+impl ReplayStats {
+    /// 3BV per second, which is only meaningful for a won game
+    pub fn three_bv_per_second(&self) -> Option<f64> {
+        (self.outcome == ReplayOutcome::Won && self.time > 0.0)
+            .then(|| self.three_bv as f64 / self.time)
+    }
+
+    /// Index of efficiency: the board's 3BV over the clicks made
+    pub fn ioe(&self) -> Option<f64> {
+        (self.outcome == ReplayOutcome::Won && self.clicks > 0)
+            .then(|| self.three_bv as f64 / self.clicks as f64)
+    }
+}
+
+fn parse_coordinate(s: &str) -> Result<Coordinate> {
+    match s.split_once(',') {
+        Some((x, y)) => Ok(Coordinate {
+            x: x.parse()?,
+            y: y.parse()?,
+        }),
+        None => Err(anyhow!("Invalid coordinate '{}'", s)),
+    }
+}
+
+/// A seeded beginner board, populated around its centre
+#[cfg(test)]
+pub fn test_board() -> GameBoard {
+    let mut board = GameBoard::new(9, 9);
+    board.seed = 1234;
+    board
+        .populate_mines_around(10, Some(Coordinate { x: 4, y: 4 }))
+        .unwrap();
+    board.populate_numerals().unwrap();
+    board
+}
+
+/// The mines of `small_test_board`, at (3, 0) and (3, 2)
+#[cfg(test)]
+pub const SMALL_TEST_MINES: [usize; 2] = [3, 11];
+
+/// A 4x3 board with two mines down its right hand side, small enough to check
+/// square by square. The video tests describe the same board in each format.
+#[cfg(test)]
+pub fn small_test_board() -> GameBoard {
+    let mut layout = vec![false; 4 * 3];
+    SMALL_TEST_MINES.iter().for_each(|i| layout[*i] = true);
+    GameBoard::new_with_layout(4, 3, &layout).unwrap()
+}
+
+/// A replay that wins a populated board by revealing its first click and then each
+/// safe square in turn, one second apart, until the board is cleared
+#[cfg(test)]
+pub fn winning_replay(mut board: GameBoard) -> Replay {
+    let mut replay = Replay::new(&board, vec![]).unwrap();
+    let safe = itertools::iproduct!(0..board.height, 0..board.width)
+        .map(|(y, x)| Coordinate { x, y })
+        .filter(|c| !board.get_square_by_coordinate(c).unwrap().is_mine())
+        .collect::<Vec<Coordinate>>();
+    std::iter::once(replay.first_click.clone())
+        .chain(safe)
+        .for_each(|coord| {
+            let sqr = board.get_square_by_coordinate(&coord).unwrap();
+            if !sqr.is_revealed && !board.is_win_configuration() {
+                board.play(coord.x, coord.y, RevealType::Reveal).unwrap();
+                replay.moves.push(ReplayMove {
+                    time: replay.moves.len() as f64,
+                    coord,
+                    play_type: RevealType::Reveal,
+                });
+            }
+        });
+    replay
+}
+
+#[test]
+fn test_replay_verify() {
+    let replay = winning_replay(test_board());
+    let time = replay.moves.last().unwrap().time;
+
+    assert!(replay.verify(time).is_ok());
+    assert!(replay.verify(time + 0.05).is_ok());
+
+    // A doctored time
+    assert!(replay.verify(1.0).is_err());
+
+    // Move times rewritten to finish sooner
+    let mut squeezed = replay.clone();
+    squeezed
+        .moves
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, m)| m.time = i as f64 * 0.02);
+    let squeezed_time = squeezed.moves.last().unwrap().time;
+    assert!(squeezed.verify(squeezed_time).is_err());
+
+    let mut late = replay.clone();
+    late.moves.iter_mut().for_each(|m| m.time += 5.0);
+    assert!(late.verify(time + 5.0).is_err());
+
+    let mut hurried = replay.clone();
+    hurried.moves[2].time = hurried.moves[1].time + 0.001;
+    assert!(hurried.verify(time).is_err());
+
+    // Not finished
+    let mut unfinished = replay.clone();
+    unfinished.moves.pop();
+    assert!(unfinished.verify(time).is_err());
+
+    // Out of order
+    let mut reordered = replay.clone();
+    reordered.moves[1].time = 1000.0;
+    assert!(reordered.verify(time).is_err());
+
+    // A different board
+    let mut reseeded = replay.clone();
+    reseeded.seed += 1;
+    assert!(reseeded.verify(time).is_err());
+
+    // Flags don't change the outcome
+    let board = replay.board().unwrap();
+    let mine = itertools::iproduct!(0..9, 0..9)
+        .map(|(y, x)| Coordinate { x, y })
+        .find(|c| board.get_square_by_coordinate(c).unwrap().is_mine())
+        .unwrap();
+    let mut flagged = replay.clone();
+    flagged.moves.insert(
+        0,
+        ReplayMove {
+            time: 0.0,
+            coord: mine,
+            play_type: RevealType::Flag,
+        },
+    );
+    assert!(flagged.verify(time).is_ok());
+    assert_eq!(flagged.flags_used(), 1);
+}
+
+#[test]
+fn test_replay_code() -> Result<()> {
+    let mut replay = winning_replay(test_board());
+    replay.use_question_marks = true;
+    replay.moves[1].time = 1.234;
+    replay.moves[2].play_type = RevealType::Flag;
+
+    let code = replay.to_code();
+    assert!(code.starts_with("r1:9x9x10:4d2:4,4:q:0,4,4,R;1234,"));
+    assert_eq!(Replay::from_code(&code)?, replay);
+
+    assert!(Replay::from_code("r2:9x9x10:4d2:4,4::").is_err());
+    assert!(Replay::from_code("r1:9x9:4d2:4,4::").is_err());
+    assert!(Replay::from_code("r1:9x9x10:4d2:4,4::0,4,4,X").is_err());
+    assert!(Replay::from_code("r1:9x9x10:4d2:4,4::").is_ok());
+    assert!(Replay::from_code("r1:9x9x81:4d2:4,4::").is_err());
+    assert!(Replay::from_code("r1:9x9x4294967295:1:4,4::").is_err());
+    let overfull = Replay {
+        num_mines: u32::MAX,
+        ..Replay::from_code("r1:9x9x10:4d2:4,4::")?
+    };
+    assert!(overfull.board().is_err());
+
+    // A board that wasn't generated from its seed keeps its layout
+    let mut layout = vec![false; 9 * 9];
+    layout[..10].iter_mut().for_each(|m| *m = true);
+    let mut board = GameBoard::new_with_layout(9, 9, &layout).unwrap();
+    board.first_click = Some(Coordinate { x: 8, y: 8 });
+    let replay = winning_replay(board);
+    assert_eq!(replay.layout, Some(layout));
+    assert!(replay.to_code().starts_with("r1:9x9x10:mffc0"));
+    assert_eq!(Replay::from_code(&replay.to_code())?, replay);
+    assert!(replay.verify(replay.moves.last().unwrap().time).is_ok());
+
+    // Hexagonal boards are replayed with their own neighbors
+    let mut board = GameBoard::new(9, 9);
+    board.seed = 1234;
+    board.variant.neighborhood = Neighborhood::Hex;
+    board
+        .populate_mines_around(10, Some(Coordinate { x: 4, y: 4 }))
+        .unwrap();
+    board.populate_numerals().unwrap();
+    let replay = winning_replay(board);
+    assert!(replay.to_code().starts_with("r1:9x9x10:4d2:4,4:h:"));
+    assert_eq!(Replay::from_code(&replay.to_code())?, replay);
+    assert!(replay.verify(replay.moves.last().unwrap().time).is_ok());
+    let mut squared = replay.clone();
+    squared.variant.neighborhood = Neighborhood::Square;
+    assert!(squared.verify(replay.moves.last().unwrap().time).is_err());
+
+    // As are boards that wrap, which can't be hexagonal
+    let mut board = GameBoard::new(9, 9);
+    board.seed = 1234;
+    board.variant.topology = Topology::Torus;
+    board
+        .populate_mines_around(10, Some(Coordinate { x: 0, y: 0 }))
+        .unwrap();
+    board.populate_numerals().unwrap();
+    let replay = winning_replay(board);
+    assert!(replay.to_code().starts_with("r1:9x9x10:4d2:0,0:w:"));
+    assert_eq!(Replay::from_code(&replay.to_code())?, replay);
+    assert!(replay.verify(replay.moves.last().unwrap().time).is_ok());
+    assert!(Replay::from_code("r1:9x9x10:4d2:4,4:hw:").is_err());
+    assert!(Replay::from_code("r1:9x9x10:4d2:4,4:wq:").is_err());
+    assert!(Replay::from_code("r1:9x9x10:4d2:4,4:qq:").is_err());
+
+    // Each other neighborhood has its own letter, and a board has only one
+    [
+        (Neighborhood::Knight, "kw"),
+        (Neighborhood::Orthogonal, "ow"),
+    ]
+    .iter()
+    .try_for_each(|(neighborhood, rules)| {
+        let mut board = GameBoard::new(9, 9);
+        board.seed = 1234;
+        board.variant = Variant::new(*neighborhood, Topology::Torus);
+        board
+            .populate_mines_around(10, Some(Coordinate { x: 4, y: 4 }))
+            .unwrap();
+        board.populate_numerals().unwrap();
+        let replay = winning_replay(board);
+        assert!(replay
+            .to_code()
+            .starts_with(&format!("r1:9x9x10:4d2:4,4:{}:", rules)));
+        assert_eq!(Replay::from_code(&replay.to_code())?, replay);
+        assert!(replay.verify(replay.moves.last().unwrap().time).is_ok());
+        anyhow::Ok(())
+    })?;
+    assert!(Replay::from_code("r1:9x9x10:4d2:4,4:hk:").is_err());
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use egui::Color32;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_polygon_mut, draw_polygon_mut};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+
+use crate::persist::write_atomic;
+use crate::render::{image_size, BoardRenderer, RenderOptions, MIN_SQUARE_SIZE};
+use crate::replay::{Replay, MAX_MOVE_TIME};
+
+pub const MIN_SPEED: f64 = 0.1;
+pub const MAX_SPEED: f64 = 10.0;
+
+/// The height of the timer strip under the board, in pixels
+const TIMER_HEIGHT: u32 = 24;
+
+/// How long the empty board is shown before the first move, in seconds of playback
+const LEAD_IN: f64 = 0.5;
+
+/// How long the final board is held before the animation loops, in seconds of
+/// playback
+const HOLD: f64 = 3.0;
+
+/// GIF frame delays are counted in hundredths of a second, and most viewers slow
+/// anything shorter than two of them
+const MIN_FRAME_CENTISECONDS: u32 = 2;
+
+/// The longest a GIF frame can be shown, in hundredths of a second
+const MAX_FRAME_CENTISECONDS: u32 = u16::MAX as u32;
+
+/// The most timer ticks shown in a pause between moves. After a longer pause the
+/// timer jumps ahead with the next move, rather than spending a frame on every idle
+/// second.
+const MAX_IDLE_TICKS: u32 = 30;
+
+/// The segments lit for each digit, in the order top, upper right, lower right,
+/// bottom, lower left, upper left, middle
+const SEGMENTS: [[bool; 7]; 10] = [
+    [true, true, true, true, true, true, false],
+    [false, true, true, false, false, false, false],
+    [true, true, false, true, true, false, true],
+    [true, true, true, true, false, false, true],
+    [false, true, true, false, false, true, true],
+    [true, false, true, true, false, true, true],
+    [true, false, true, true, true, true, true],
+    [true, true, true, false, false, false, false],
+    [true, true, true, true, true, true, true],
+    [true, true, true, true, false, true, true],
+];
+
+/// The outline of the mouse pointer, tip first, on a 16 pixel square
+const CURSOR: [(i32, i32); 7] = [(0, 0), (0, 11), (3, 8), (5, 12), (7, 11), (5, 7), (9, 7)];
+
+/// How a replay is animated
+#[derive(Debug, Clone)]
+pub struct GifOptions {
+    pub render: RenderOptions,
+
+    /// Playback speed, as a multiple of the pace of the game
+    pub speed: f64,
+
+    /// The largest size of the animation, in pixels. Squares are drawn smaller than
+    /// asked for if the board wouldn't otherwise fit.
+    pub max_width: u32,
+    pub max_height: u32,
+
+    /// Show the pointer on the square of each move
+    pub cursor: bool,
+
+    /// Show the game timer under the board
+    pub timer: bool,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        GifOptions {
+            render: RenderOptions::default(),
+            speed: 1.0,
+            max_width: 1024,
+            max_height: 1024,
+            cursor: true,
+            timer: true,
+        }
+    }
+}
+
+/// A frame of the animation: the board after a number of moves, with the timer at
+/// `time`, shown from `time` in the game
+#[derive(Debug, Clone, PartialEq)]
+struct FrameSpec {
+    moves: usize,
+    time: f64,
+}
+
+/// The frames of a game whose moves were made at `times`. Each move gets a frame,
+/// and with the timer shown, so does every whole second between them, up to
+/// `MAX_IDLE_TICKS` in any one pause.
+fn frame_specs(times: &[f64], timer: bool) -> Vec<FrameSpec> {
+    let mut specs = vec![];
+    let mut start = 0.0;
+    for moves in 0..=times.len() {
+        let end = times.get(moves).copied().unwrap_or(start);
+        specs.push(FrameSpec { moves, time: start });
+        if timer {
+            let mut second = start.floor() + 1.0;
+            let last_tick = start.floor() + MAX_IDLE_TICKS as f64;
+            while second < end && second <= last_tick {
+                specs.push(FrameSpec {
+                    moves,
+                    time: second,
+                });
+                second += 1.0;
+            }
+        }
+        start = end;
+    }
+    specs
+}
+
+/// The frame delays, in hundredths of a second. Each frame is placed on the
+/// playback timeline, rounded, and shown until the next one, so rounding never adds
+/// up over a long game. Frames too short to show are given None, to be dropped.
+fn frame_delays(specs: &[FrameSpec], speed: f64) -> Vec<Option<u32>> {
+    let at = |spec: &FrameSpec| {
+        let time = spec.time.clamp(0.0, MAX_MOVE_TIME);
+        ((LEAD_IN + time / speed) * 100.0).round() as u64
+    };
+    let mut delays = vec![];
+    let mut shown = 0;
+    specs.iter().enumerate().for_each(|(i, spec)| {
+        let start = if i == 0 { 0 } else { at(spec).max(shown) };
+        let end = match specs.get(i + 1) {
+            Some(next) => at(next),
+            None => start.saturating_add((HOLD * 100.0) as u64),
+        };
+        if end >= start.saturating_add(MIN_FRAME_CENTISECONDS as u64) {
+            delays.push(Some((end - start).min(MAX_FRAME_CENTISECONDS as u64) as u32));
+            shown = end;
+        } else {
+            delays.push(None);
+        }
+    });
+    delays
+}
+
+/// Draws the time in seven segment digits, right aligned in the strip
+fn draw_timer(image: &mut RgbaImage, top: u32, time: f64, color: Color32) {
+    let lit = Rgba(color.to_srgba_unmultiplied());
+    let height = TIMER_HEIGHT as i32 * 2 / 3;
+    let width = height / 2;
+    let thickness = (height / 8).max(1);
+    let y = top as i32 + (TIMER_HEIGHT as i32 - height) / 2;
+    let mut x = image.width() as i32 - thickness * 3;
+
+    format!("{:.1}", time).chars().rev().for_each(|c| {
+        let Some(digit) = c.to_digit(10) else {
+            // The decimal point
+            x -= thickness * 2;
+            draw_filled_rect_mut(
+                image,
+                Rect::at(x, y + height - thickness).of_size(thickness as u32, thickness as u32),
+                lit,
+            );
+            x -= thickness;
+            return;
+        };
+        x -= width;
+        let half = height / 2;
+        let horizontal = |dy: i32| Rect::at(x, y + dy).of_size(width as u32, thickness as u32);
+        let vertical = |dx: i32, dy: i32| {
+            Rect::at(x + dx, y + dy).of_size(thickness as u32, (half + thickness / 2) as u32)
+        };
+        let segments = [
+            horizontal(0),
+            vertical(width - thickness, 0),
+            vertical(width - thickness, half),
+            horizontal(height - thickness),
+            vertical(0, half),
+            vertical(0, 0),
+            horizontal(half - thickness / 2),
+        ];
+        segments
+            .iter()
+            .zip(SEGMENTS[digit as usize])
+            .filter(|(_, on)| *on)
+            .for_each(|(r, _)| draw_filled_rect_mut(image, *r, lit));
+        x -= thickness * 2;
+    });
+}
+
+/// Draws the pointer with its tip at the centre of a square
+fn draw_cursor(image: &mut RgbaImage, (cx, cy): (f32, f32), square_size: u32) {
+    let scale = square_size as f32 / 16.0;
+    let points = CURSOR
+        .iter()
+        .map(|(x, y)| Point::new(cx + *x as f32 * scale, cy + *y as f32 * scale))
+        .collect::<Vec<Point<f32>>>();
+    draw_polygon_mut(
+        image,
+        &points
+            .iter()
+            .map(|p| Point::new(p.x.round() as i32, p.y.round() as i32))
+            .collect::<Vec<Point<i32>>>(),
+        Rgba([255, 255, 255, 255]),
+    );
+    draw_hollow_polygon_mut(image, &points, Rgba([0, 0, 0, 255]));
+}
+
+/// Animates a replay, from the empty board to the end of the game, as a looping GIF.
+/// Each frame is encoded as soon as it's drawn, so a long game doesn't have to fit
+/// in memory.
+pub fn encode_gif(replay: &Replay, options: &GifOptions) -> Result<Vec<u8>> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&options.speed) {
+        return Err(anyhow!(
+            "The speed must be between {} and {}",
+            MIN_SPEED,
+            MAX_SPEED
+        ));
+    }
+    let mut board = replay.board()?;
+
+    // Shrink the squares to fit, leaving room for the timer
+    let timer_height = if options.timer { TIMER_HEIGHT } else { 0 };
+    let fits = |square_size: u32| {
+        let (width, height) = image_size(&board, square_size);
+        width <= options.max_width && height + timer_height <= options.max_height
+    };
+    let mut square_size = options.render.square_size;
+    while square_size >= MIN_SQUARE_SIZE && !fits(square_size) {
+        square_size -= 1;
+    }
+    if square_size < MIN_SQUARE_SIZE {
+        return Err(anyhow!(
+            "A {}x{} board doesn't fit in {}x{} pixels",
+            board.width,
+            board.height,
+            options.max_width,
+            options.max_height
+        ));
+    }
+    let renderer = BoardRenderer::new(RenderOptions {
+        square_size,
+        ..options.render.clone()
+    })?;
+
+    // Only the moves up to the end of the game are shown
+    let played = replay.play_out()?.1.clicks as usize;
+    let moves = &replay.moves[..played];
+    let specs = frame_specs(
+        &moves.iter().map(|m| m.time).collect::<Vec<f64>>(),
+        options.timer,
+    );
+    let delays = frame_delays(&specs, options.speed);
+
+    let (width, height) = renderer.image_size(&board);
+    let strip = Rgba(options.render.palette.border.to_srgba_unmultiplied());
+    let mut played = 0;
+    let mut board_image = renderer.render(&board)?;
+    let mut bytes = vec![];
+    let mut encoder = GifEncoder::new_with_speed(&mut bytes, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    for (spec, delay) in specs.iter().zip(delays) {
+        while played < spec.moves {
+            let m = &moves[played];
+            board
+                .play(m.coord.x, m.coord.y, m.play_type.clone())
+                .map_err(|e| anyhow!("Move {} is invalid: {:?}", played + 1, e))?;
+            played += 1;
+            board_image = renderer.render(&board)?;
+        }
+        let Some(delay) = delay else {
+            continue;
+        };
+
+        let mut image = RgbaImage::from_pixel(width, height + timer_height, strip);
+        image::imageops::replace(&mut image, &board_image, 0, 0);
+        if options.cursor && spec.moves > 0 {
+            let center = renderer.center(&board, &moves[spec.moves - 1].coord);
+            draw_cursor(&mut image, center, square_size);
+        }
+        if options.timer {
+            draw_timer(&mut image, height, spec.time, options.render.palette.flag);
+        }
+        encoder.encode_frame(Frame::from_parts(
+            image,
+            0,
+            0,
+            Delay::from_numer_denom_ms(delay * 10, 1),
+        ))?;
+    }
+    drop(encoder);
+    Ok(bytes)
+}
+
+/// Animates a replay to a GIF file
+pub fn save_gif(replay: &Replay, path: &Path, options: &GifOptions) -> Result<()> {
+    write_atomic(path, &encode_gif(replay, options)?)
+}
+
+#[test]
+fn test_frame_timing() {
+    let specs = frame_specs(&[0.0, 0.5, 2.5], true);
+    assert_eq!(
+        specs
+            .iter()
+            .map(|s| (s.moves, s.time))
+            .collect::<Vec<(usize, f64)>>(),
+        [(0, 0.0), (1, 0.0), (2, 0.5), (2, 1.0), (2, 2.0), (3, 2.5)]
+    );
+    assert_eq!(frame_specs(&[0.0, 0.5, 2.5], false).len(), 4);
+
+    // A long pause only ticks for so long, and is held as one frame until the next
+    // move
+    let idle = frame_specs(&[0.0, 10_000_000.0], true);
+    assert_eq!(idle.len(), 3 + MAX_IDLE_TICKS as usize);
+    let delays = frame_delays(&idle, 0.1);
+    assert_eq!(delays[delays.len() - 2], Some(MAX_FRAME_CENTISECONDS));
+
+    // Absurd times are clamped rather than overflowing
+    let absurd = frame_delays(&frame_specs(&[0.0, 1e300], false), MIN_SPEED);
+    assert_eq!(absurd.len(), 3);
+    assert_eq!(absurd[2], Some((HOLD * 100.0) as u32));
+
+    // The empty board is shown for the lead in, the final board is held, and the
+    // whole animation keeps pace with the game
+    let delays = frame_delays(&specs, 1.0);
+    assert_eq!(
+        delays,
+        [Some(50), Some(50), Some(50), Some(100), Some(50), Some(300)]
+    );
+
+    // At double speed, the first move's frame is too short to show
+    let delays = frame_delays(&frame_specs(&[0.0, 0.02, 1.0], false), 2.0);
+    assert_eq!(delays, [Some(50), None, Some(49), Some(300)]);
+}
+
+#[test]
+fn test_encode_gif() -> Result<()> {
+    use image::AnimationDecoder;
+
+    let replay = crate::replay::winning_replay(crate::replay::test_board());
+    let options = GifOptions {
+        max_width: 9 * 16 + 1,
+        ..Default::default()
+    };
+    let bytes = encode_gif(&replay, &options)?;
+    let frames = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(bytes))?
+        .into_frames()
+        .collect_frames()?;
+
+    // One frame for the empty board and for each move, and with a move a second, no
+    // ticks of the timer between them
+    assert_eq!(frames.len(), replay.moves.len() + 1);
+    assert_eq!(
+        frames[0].buffer().dimensions(),
+        (9 * 16 + 1, 9 * 16 + 1 + TIMER_HEIGHT)
+    );
+
+    // Too small to fit, or too fast
+    assert!(encode_gif(
+        &replay,
+        &GifOptions {
+            max_width: 40,
+            ..Default::default()
+        }
+    )
+    .is_err());
+    assert!(encode_gif(
+        &replay,
+        &GifOptions {
+            speed: 100.0,
+            ..Default::default()
+        }
+    )
+    .is_err());
+    Ok(())
+}
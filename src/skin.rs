@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::persist::{config_dir, data_dir, legacy_dir};
+
+/// The largest tile image read from a skin, in bytes. Tile images are small, so
+/// anything bigger isn't worth reading into memory.
+const MAX_TILE_SIZE: u64 = 1024 * 1024;
+
+/// A single replaceable image used to draw the board and the face button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkinTile {
+    Numeral(u32),
+    Flag,
+    Mine,
+    Blast,
+    MisFlag,
+    FlagCorrect,
+    Question,
+    Unrevealed,
+    Blank,
+    FaceHappy,
+    FaceWin,
+    FaceLoss,
+}
+
+impl SkinTile {
+    pub fn all() -> Vec<SkinTile> {
+        let mut tiles = (1..=8).map(SkinTile::Numeral).collect::<Vec<SkinTile>>();
+        tiles.extend([
+            SkinTile::Flag,
+            SkinTile::Mine,
+            SkinTile::Blast,
+            SkinTile::MisFlag,
+            SkinTile::FlagCorrect,
+            SkinTile::Question,
+            SkinTile::Unrevealed,
+            SkinTile::Blank,
+            SkinTile::FaceHappy,
+            SkinTile::FaceWin,
+            SkinTile::FaceLoss,
+        ]);
+        tiles
+    }
+
+    /// The name of the image file for this tile within a skin
+    pub fn file_name(&self) -> String {
+        match self {
+            SkinTile::Numeral(n) => format!("{}.png", n),
+            SkinTile::Flag => "flag.png".to_string(),
+            SkinTile::Mine => "mine.png".to_string(),
+            SkinTile::Blast => "blast.png".to_string(),
+            SkinTile::MisFlag => "misflag.png".to_string(),
+            SkinTile::FlagCorrect => "chk.png".to_string(),
+            SkinTile::Question => "unk.png".to_string(),
+            SkinTile::Unrevealed => "unrevealed.png".to_string(),
+            SkinTile::Blank => "blank.png".to_string(),
+            SkinTile::FaceHappy => "happy.png".to_string(),
+            SkinTile::FaceWin => "win.png".to_string(),
+            SkinTile::FaceLoss => "loss.png".to_string(),
+        }
+    }
+
+    /// The image bundled with the game for this tile
+    pub fn builtin(&self) -> &'static [u8] {
+        match self {
+            SkinTile::Numeral(1) => include_bytes!("../assets/1.png"),
+            SkinTile::Numeral(2) => include_bytes!("../assets/2.png"),
+            SkinTile::Numeral(3) => include_bytes!("../assets/3.png"),
+            SkinTile::Numeral(4) => include_bytes!("../assets/4.png"),
+            SkinTile::Numeral(5) => include_bytes!("../assets/5.png"),
+            SkinTile::Numeral(6) => include_bytes!("../assets/6.png"),
+            SkinTile::Numeral(7) => include_bytes!("../assets/7.png"),
+            SkinTile::Numeral(_) => include_bytes!("../assets/8.png"),
+            SkinTile::Flag => include_bytes!("../assets/flag.png"),
+            SkinTile::Mine => include_bytes!("../assets/mine.png"),
+            SkinTile::Blast => include_bytes!("../assets/blast.png"),
+            SkinTile::MisFlag => include_bytes!("../assets/x.png"),
+            SkinTile::FlagCorrect => include_bytes!("../assets/chk.png"),
+            SkinTile::Question => include_bytes!("../assets/unk.png"),
+            SkinTile::Unrevealed => include_bytes!("../assets/unrevealed.png"),
+            SkinTile::Blank => include_bytes!("../assets/blank.png"),
+            SkinTile::FaceHappy => include_bytes!("../assets/happy.png"),
+            SkinTile::FaceWin => include_bytes!("../assets/win.png"),
+            SkinTile::FaceLoss => include_bytes!("../assets/loss.png"),
+        }
+    }
+
+    fn from_file_name(file_name: &str) -> Option<SkinTile> {
+        SkinTile::all()
+            .into_iter()
+            .find(|t| t.file_name().eq_ignore_ascii_case(file_name))
+    }
+
+    /// The tile for a path within a skin, found by the path's file name
+    fn from_path(path: &str) -> Option<SkinTile> {
+        SkinTile::from_file_name(Path::new(path).file_name()?.to_str()?)
+    }
+}
+
+/// Reads a tile image, refusing images larger than `MAX_TILE_SIZE`
+fn read_tile<R: Read>(name: &str, reader: R) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    reader.take(MAX_TILE_SIZE + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_TILE_SIZE {
+        return Err(anyhow!("The skin image '{}' is too large", name));
+    }
+    Ok(bytes)
+}
+
+/// A user supplied set of tile images, loaded from either a directory or a zip
+/// file in the skins directory. Tiles the skin does not provide are drawn with
+/// the built-in images.
+#[derive(Debug, Clone, Default)]
+pub struct Skin {
+    pub name: String,
+    tiles: HashMap<SkinTile, Vec<u8>>,
+}
+
+impl Skin {
+    /// Builds a skin from (file name, contents) pairs, ignoring unrecognized files.
+    pub fn from_files<I>(name: &str, files: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Vec<u8>)>,
+    {
+        Skin {
+            name: name.to_string(),
+            tiles: files
+                .into_iter()
+                .filter_map(|(f, bytes)| SkinTile::from_path(&f).map(|t| (t, bytes)))
+                .collect(),
+        }
+    }
+
+    pub fn load_dir(name: &str, path: &Path) -> Result<Self> {
+        let files = std::fs::read_dir(path)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|f| SkinTile::from_path(f).is_some())
+            .map(|f| {
+                let bytes = read_tile(&f, File::open(path.join(&f))?)?;
+                Ok((f, bytes))
+            })
+            .collect::<Result<Vec<(String, Vec<u8>)>>>()?;
+        Ok(Skin::from_files(name, files))
+    }
+
+    pub fn load_zip(name: &str, path: &Path) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        let mut files = vec![];
+        for i in 0..archive.len() {
+            let f = archive.by_index(i)?;
+            if f.is_file() && SkinTile::from_path(f.name()).is_some() {
+                let name = f.name().to_string();
+                let bytes = read_tile(&name, f)?;
+                files.push((name, bytes));
+            }
+        }
+        Ok(Skin::from_files(name, files))
+    }
+
+    /// Skins are looked for in the config directory, then the data directory
+    /// where earlier versions looked for them, then the legacy directory
+    pub fn skins_dirs() -> Vec<PathBuf> {
+        [
+            config_dir().map(|d| d.join("skins")),
+            data_dir().map(|d| d.join("skins")),
+            legacy_dir().map(|d| d.join("minesofrust-skins")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Lists the names of the skins found in the skins directories
+    pub fn available() -> Vec<String> {
+        let mut names = Skin::skins_dirs()
+            .iter()
+            .filter_map(|d| std::fs::read_dir(d).ok())
+            .flat_map(|rd| rd.filter_map(|e| e.ok()).map(|e| e.path()))
+            .filter_map(|p| {
+                let is_zip = p.extension().map(|e| e == "zip").unwrap_or(false);
+                if p.is_dir() || is_zip {
+                    p.file_stem().map(|s| s.to_string_lossy().to_string())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Loads a skin by name from the skins directories. Within a directory, a
+    /// skin directory takes precedence over a zip file of the same name.
+    pub fn load(name: &str) -> Result<Self> {
+        for skins_dir in Skin::skins_dirs() {
+            let dir = skins_dir.join(name);
+            let zip = skins_dir.join(format!("{}.zip", name));
+            if dir.is_dir() {
+                return Skin::load_dir(name, &dir);
+            } else if zip.is_file() {
+                return Skin::load_zip(name, &zip);
+            }
+        }
+        Err(anyhow!("Skin '{}' not found", name))
+    }
+
+    pub fn get(&self, tile: SkinTile) -> Option<&[u8]> {
+        self.tiles.get(&tile).map(|b| b.as_slice())
+    }
+
+    pub fn num_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+}
+
+#[test]
+fn test_skin_from_files() {
+    let skin = Skin::from_files(
+        "test",
+        vec![
+            ("1.png".to_string(), vec![1]),
+            ("branded/FLAG.png".to_string(), vec![2]),
+            ("misflag.png".to_string(), vec![3]),
+            ("readme.txt".to_string(), vec![4]),
+            ("9.png".to_string(), vec![5]),
+        ],
+    );
+    assert_eq!(skin.num_tiles(), 3);
+    assert_eq!(skin.get(SkinTile::Numeral(1)), Some(&[1_u8][..]));
+    assert_eq!(skin.get(SkinTile::Flag), Some(&[2_u8][..]));
+    assert_eq!(skin.get(SkinTile::MisFlag), Some(&[3_u8][..]));
+    assert_eq!(skin.get(SkinTile::Mine), None);
+}
+
+#[test]
+fn test_skin_load_dir_and_zip() -> Result<()> {
+    use std::io::Write;
+
+    let base = std::env::temp_dir().join(format!("minesofrust-skin-test-{}", std::process::id()));
+    let dir = base.join("branded");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("mine.png"), [7, 7])?;
+    std::fs::write(dir.join("notes.txt"), [0])?;
+
+    let skin = Skin::load_dir("branded", &dir)?;
+    assert_eq!(skin.num_tiles(), 1);
+    assert_eq!(skin.get(SkinTile::Mine), Some(&[7_u8, 7][..]));
+
+    let zip_path = base.join("branded.zip");
+    let mut zw = zip::ZipWriter::new(File::create(&zip_path)?);
+    zw.start_file("branded/happy.png", zip::write::FileOptions::default())?;
+    zw.write_all(&[9])?;
+    zw.start_file("branded/readme.txt", zip::write::FileOptions::default())?;
+    zw.write_all(&vec![0; MAX_TILE_SIZE as usize + 1])?;
+    zw.finish()?;
+
+    let skin = Skin::load_zip("branded", &zip_path)?;
+    assert_eq!(skin.num_tiles(), 1);
+    assert_eq!(skin.get(SkinTile::FaceHappy), Some(&[9_u8][..]));
+
+    let mut zw = zip::ZipWriter::new(File::create(&zip_path)?);
+    zw.start_file("branded/mine.png", zip::write::FileOptions::default())?;
+    zw.write_all(&vec![0; MAX_TILE_SIZE as usize + 1])?;
+    zw.finish()?;
+    assert!(Skin::load_zip("branded", &zip_path).is_err());
+
+    std::fs::remove_dir_all(&base)?;
+    Ok(())
+}
@@ -1,12 +1,12 @@
-use std::fs;
-use std::fs::File;
-use std::io::Write;
+use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::constants::*;
 use crate::enums::*;
+use crate::minesweeper::{Neighborhood, Topology, Variant};
+use crate::persist::*;
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct GameSettings {
@@ -52,6 +52,39 @@ impl GameSettings {
         }
     }
 
+    /// Settings for a board of any size, such as one shared by board code. Boards the
+    /// size of a difficulty level get that level's window size.
+    pub fn for_board(width: u32, height: u32, num_mines: u32) -> Self {
+        [
+            GameSettings::beginner(),
+            GameSettings::intermediate(),
+            GameSettings::expert(),
+        ]
+        .into_iter()
+        .find(|s| s.width == width && s.height == height)
+        .map(|s| GameSettings { num_mines, ..s })
+        .unwrap_or(GameSettings {
+            width,
+            height,
+            num_mines,
+            use_numerals: true,
+            ui_width: (UI_SQUARE_SIZE * width as f32 + UI_MARGIN_WIDTH)
+                .max(DEFAULT_BEGINNER_UI_WIDTH),
+            ui_height: (UI_SQUARE_SIZE * height as f32 + UI_MARGIN_HEIGHT)
+                .max(DEFAULT_BEGINNER_UI_HEIGHT),
+        })
+    }
+
+    /// The window size for a board of these settings. Hexagonal boards are half a
+    /// square wider, since alternate rows are shifted.
+    pub fn ui_size(&self, neighborhood: Neighborhood) -> egui::Vec2 {
+        let extra = match neighborhood {
+            Neighborhood::Square | Neighborhood::Knight | Neighborhood::Orthogonal => 0.0,
+            Neighborhood::Hex => UI_SQUARE_SIZE / 2.0,
+        };
+        egui::vec2(self.ui_width + extra, self.ui_height)
+    }
+
     pub fn settings_for_difficulty(difficulty: &GameDifficulty) -> Self {
         match difficulty {
             GameDifficulty::Beginner => GameSettings::beginner(),
@@ -61,12 +94,35 @@ impl GameSettings {
     }
 }
 
+/// Missing fields take their default values, so fields can be added without
+/// breaking older config files.
 #[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct AppState {
     pub difficulty: GameDifficulty,
     pub left_click_chord: bool,
     pub theme: VisualTheme,
     pub fog_of_war: bool,
+
+    pub input_scheme: InputScheme,
+
+    pub question_marks: bool,
+
+    pub question_blocks_reveal: bool,
+
+    pub skin: Option<String>,
+
+    pub renderer: TileRenderer,
+
+    pub large_numerals: bool,
+
+    pub animation_speed: AnimationSpeed,
+
+    /// The kind of board new games are played on
+    pub neighborhood: Neighborhood,
+
+    /// Whether the boards of new games wrap around their edges
+    pub topology: Topology,
 }
 
 impl Default for AppState {
@@ -76,36 +132,71 @@ impl Default for AppState {
             left_click_chord: false,
             theme: VisualTheme::Dark,
             fog_of_war: false,
+            input_scheme: InputScheme::Standard,
+            question_marks: false,
+            question_blocks_reveal: false,
+            skin: None,
+            renderer: TileRenderer::Images,
+            large_numerals: false,
+            animation_speed: AnimationSpeed::Off,
+            neighborhood: Neighborhood::Square,
+            topology: Topology::Bounded,
         }
     }
 }
 
+impl Versioned for AppState {
+    const VERSION: u32 = 1;
+}
+
 impl AppState {
-    pub fn load_from_userhome() -> Result<Self> {
-        let config_file_path = dirs::home_dir().unwrap().join(".apoapsys/minesofrust.toml");
+    pub fn file_path() -> Result<PathBuf> {
+        resolve_path(config_dir(), "settings.toml", "minesofrust.toml")
+    }
+
+    pub fn load() -> Result<Self> {
+        let config_file_path = AppState::file_path()?;
         if config_file_path.exists() {
             println!(
                 "Window state config file exists at path: {:?}",
                 config_file_path
             );
-            let t = std::fs::read_to_string(config_file_path)?;
-            Ok(toml::from_str(&t)?)
+            load_versioned(&config_file_path)
         } else {
             println!("Window state config file does not exist. Will be created on exit");
             Err(anyhow!("Config file does not exist"))
         }
     }
 
-    pub fn save_to_userhome(&self) {
-        let toml_str = toml::to_string(&self).unwrap();
-        let apoapsys_config_dir = dirs::home_dir().unwrap().join(".apoapsys/");
-        if !apoapsys_config_dir.exists() {
-            fs::create_dir(&apoapsys_config_dir).expect("Failed to create config directory");
-        }
-        let config_file_path = apoapsys_config_dir.join("minesofrust.toml");
-        let mut f = File::create(config_file_path).expect("Failed to create config file");
-        f.write_all(toml_str.as_bytes())
-            .expect("Failed to write to config file");
-        println!("{}", toml_str);
+    pub fn save(&self) -> Result<()> {
+        save_versioned(self, &AppState::file_path()?)
+    }
+
+    /// The kind of board new games are played on
+    pub fn variant(&self) -> Variant {
+        Variant::new(self.neighborhood, self.topology)
     }
 }
+
+#[test]
+fn test_app_state_versioned_round_trip() -> Result<()> {
+    // A config file from before versioning, missing every field added since
+    let legacy = "difficulty = \"Expert\"\nleft_click_chord = true\ntheme = \"Light\"\n";
+    let state: AppState = from_versioned_str(legacy)?;
+    assert!(state.difficulty == GameDifficulty::Expert);
+    assert!(state.left_click_chord);
+    assert_eq!(state.theme, VisualTheme::Light);
+    assert!(!state.fog_of_war);
+
+    let state = AppState {
+        theme: VisualTheme::Custom("Solarized".to_string()),
+        skin: Some("branded".to_string()),
+        animation_speed: AnimationSpeed::Fast,
+        ..Default::default()
+    };
+    let reloaded: AppState = from_versioned_str(&to_versioned_string(&state)?)?;
+    assert_eq!(reloaded.theme, state.theme);
+    assert_eq!(reloaded.skin, state.skin);
+    assert_eq!(reloaded.animation_speed, AnimationSpeed::Fast);
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use egui::{Color32, ColorImage, Rect, TextureHandle, TextureOptions};
+use image::{Rgba, RgbaImage};
+
+use crate::skin::{Skin, SkinTile};
+
+/// A loaded tile image. Built-in images are drawn with the active palette's colors,
+/// while images supplied by a skin are drawn exactly as provided.
+#[derive(Clone)]
+struct TileTexture {
+    texture: TextureHandle,
+    tintable: bool,
+
+    /// The built-in flag is split into a tintable cloth and this untinted pole
+    flag_pole: Option<TextureHandle>,
+}
+
+/// The set of textures used to draw the board and the face button.
+///
+/// The built-in numerals and the flag's cloth are converted to white masks so that
+/// they can be tinted to any color.
+#[derive(Clone)]
+pub struct TileTextures {
+    tiles: HashMap<SkinTile, TileTexture>,
+}
+
+/// The flag cloth is the strongly red part of the image
+pub fn is_flag_cloth(p: &Rgba<u8>) -> bool {
+    p[0] as i32 > p[1] as i32 + 64
+}
+
+/// Splits an image in two. Pixels selected by `is_mask` become white, keeping their
+/// alpha, in the first image. All other pixels are kept as-is in the second.
+pub fn split_mask(
+    image: &RgbaImage,
+    is_mask: impl Fn(&Rgba<u8>) -> bool,
+) -> (RgbaImage, RgbaImage) {
+    let mut mask = RgbaImage::new(image.width(), image.height());
+    let mut rest = RgbaImage::new(image.width(), image.height());
+    image.enumerate_pixels().for_each(|(x, y, p)| {
+        if is_mask(p) {
+            mask.put_pixel(x, y, Rgba([255, 255, 255, p[3]]));
+        } else {
+            rest.put_pixel(x, y, *p);
+        }
+    });
+    (mask, rest)
+}
+
+fn to_color_image(image: &RgbaImage) -> ColorImage {
+    ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    )
+}
+
+fn load_builtin(ctx: &egui::Context, tile: SkinTile) -> TileTexture {
+    let image = image::load_from_memory(tile.builtin())
+        .expect("Failed to decode built-in image")
+        .into_rgba8();
+    let name = format!("builtin-{}", tile.file_name());
+
+    match tile {
+        SkinTile::Numeral(_) => {
+            let (mask, _) = split_mask(&image, |_| true);
+            TileTexture {
+                texture: ctx.load_texture(name, to_color_image(&mask), TextureOptions::LINEAR),
+                tintable: true,
+                flag_pole: None,
+            }
+        }
+        SkinTile::Flag => {
+            let (cloth, pole) = split_mask(&image, is_flag_cloth);
+            TileTexture {
+                texture: ctx.load_texture(name, to_color_image(&cloth), TextureOptions::LINEAR),
+                tintable: true,
+                flag_pole: Some(ctx.load_texture(
+                    "builtin-flag-pole",
+                    to_color_image(&pole),
+                    TextureOptions::LINEAR,
+                )),
+            }
+        }
+        _ => TileTexture {
+            texture: ctx.load_texture(name, to_color_image(&image), TextureOptions::LINEAR),
+            tintable: matches!(tile, SkinTile::Unrevealed | SkinTile::Blank),
+            flag_pole: None,
+        },
+    }
+}
+
+fn load_skinned(ctx: &egui::Context, skin: &Skin, tile: SkinTile) -> Option<TileTexture> {
+    let bytes = skin.get(tile)?;
+    match image::load_from_memory(bytes) {
+        Ok(image) => Some(TileTexture {
+            texture: ctx.load_texture(
+                format!("skin-{}-{}", skin.name, tile.file_name()),
+                to_color_image(&image.into_rgba8()),
+                TextureOptions::LINEAR,
+            ),
+            tintable: false,
+            flag_pole: None,
+        }),
+        Err(why) => {
+            println!(
+                "Failed to decode {} in skin '{}', using built-in: {}",
+                tile.file_name(),
+                skin.name,
+                why
+            );
+            None
+        }
+    }
+}
+
+impl TileTextures {
+    /// Loads every tile, preferring those supplied by `skin`.
+    pub fn load(ctx: &egui::Context, skin: Option<&Skin>) -> Self {
+        TileTextures {
+            tiles: SkinTile::all()
+                .into_iter()
+                .map(|t| {
+                    let texture = skin
+                        .and_then(|s| load_skinned(ctx, s, t))
+                        .unwrap_or_else(|| load_builtin(ctx, t));
+                    (t, texture)
+                })
+                .collect(),
+        }
+    }
+
+    /// Paints a tile into `rect`. The tint is applied only to built-in images.
+    pub fn paint(&self, ui: &egui::Ui, rect: Rect, tile: SkinTile, tint: Color32) {
+        if let Some(t) = self.tiles.get(&tile) {
+            if let Some(pole) = &t.flag_pole {
+                egui::Image::new(pole).paint_at(ui, rect);
+            }
+            let image = egui::Image::new(&t.texture);
+            if t.tintable {
+                image.tint(tint).paint_at(ui, rect);
+            } else {
+                image.paint_at(ui, rect);
+            }
+        }
+    }
+
+    /// Paints a tile into `rect` partially transparent, regardless of its source.
+    pub fn paint_faded(&self, ui: &egui::Ui, rect: Rect, tile: SkinTile, alpha: u8) {
+        if let Some(t) = self.tiles.get(&tile) {
+            egui::Image::new(&t.texture)
+                .tint(Color32::from_white_alpha(alpha))
+                .paint_at(ui, rect);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::path::Path;
+
+use anyhow::Result;
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::enums::VisualTheme;
+use crate::persist::{config_dir, legacy_dir};
+
+/// A named set of colors used to draw the game board.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Palette {
+    pub name: String,
+
+    /// Use the dark egui visuals for the rest of the interface
+    pub dark: bool,
+
+    #[serde(with = "as_hex_color")]
+    pub unrevealed: Color32,
+
+    #[serde(with = "as_hex_color")]
+    pub revealed: Color32,
+
+    #[serde(with = "as_hex_color")]
+    pub border: Color32,
+
+    #[serde(with = "as_hex_color")]
+    pub flag: Color32,
+
+    #[serde(with = "as_hex_color")]
+    pub detonated: Color32,
+
+    #[serde(with = "as_hex_color")]
+    pub misflagged: Color32,
+
+    /// Colors for the numerals 1 through 8
+    #[serde(with = "as_hex_colors")]
+    pub numerals: [Color32; 8],
+}
+
+/// The numeral colors used by the bundled numeral images
+const CLASSIC_NUMERALS: [Color32; 8] = [
+    Color32::from_rgb(0, 2, 140),
+    Color32::from_rgb(14, 82, 0),
+    Color32::from_rgb(105, 100, 0),
+    Color32::from_rgb(134, 0, 119),
+    Color32::from_rgb(221, 0, 0),
+    Color32::from_rgb(117, 0, 0),
+    Color32::from_rgb(163, 67, 0),
+    Color32::from_rgb(53, 53, 53),
+];
+
+const CLASSIC_FLAG: Color32 = Color32::from_rgb(197, 0, 0);
+
+impl Palette {
+    pub fn dark() -> Self {
+        Palette {
+            name: "Dark".to_string(),
+            dark: true,
+            unrevealed: Color32::from_rgb(0, 92, 128),
+            revealed: Color32::from_gray(60),
+            border: Color32::from_rgb(27, 27, 27),
+            flag: CLASSIC_FLAG,
+            detonated: Color32::GOLD,
+            misflagged: Color32::from_rgb(255, 188, 188),
+            numerals: CLASSIC_NUMERALS,
+        }
+    }
+
+    pub fn light() -> Self {
+        Palette {
+            name: "Light".to_string(),
+            dark: false,
+            unrevealed: Color32::from_rgb(144, 209, 255),
+            revealed: Color32::from_gray(230),
+            border: Color32::from_rgb(27, 27, 27),
+            flag: CLASSIC_FLAG,
+            detonated: Color32::GOLD,
+            misflagged: Color32::from_rgb(255, 188, 188),
+            numerals: CLASSIC_NUMERALS,
+        }
+    }
+
+    /// Avoids red/green distinctions, based on the Okabe-Ito palette
+    pub fn deuteranopia() -> Self {
+        Palette {
+            name: "Deuteranopia".to_string(),
+            dark: false,
+            unrevealed: Color32::from_rgb(153, 153, 204),
+            revealed: Color32::from_gray(235),
+            border: Color32::from_rgb(27, 27, 27),
+            flag: Color32::from_rgb(213, 94, 0),
+            detonated: Color32::from_rgb(240, 228, 66),
+            misflagged: Color32::from_rgb(204, 121, 167),
+            numerals: [
+                Color32::from_rgb(0, 114, 178),
+                Color32::from_rgb(230, 159, 0),
+                Color32::from_rgb(204, 121, 167),
+                Color32::from_rgb(0, 0, 128),
+                Color32::from_rgb(128, 64, 0),
+                Color32::from_rgb(86, 180, 233),
+                Color32::from_rgb(0, 0, 0),
+                Color32::from_rgb(110, 110, 110),
+            ],
+        }
+    }
+
+    /// Like deuteranopia, but avoids dark reds that read as black
+    pub fn protanopia() -> Self {
+        Palette {
+            name: "Protanopia".to_string(),
+            dark: false,
+            unrevealed: Color32::from_rgb(153, 170, 204),
+            revealed: Color32::from_gray(235),
+            border: Color32::from_rgb(27, 27, 27),
+            flag: Color32::from_rgb(230, 159, 0),
+            detonated: Color32::from_rgb(240, 228, 66),
+            misflagged: Color32::from_rgb(86, 180, 233),
+            numerals: [
+                Color32::from_rgb(0, 114, 178),
+                Color32::from_rgb(200, 140, 0),
+                Color32::from_rgb(120, 60, 160),
+                Color32::from_rgb(0, 0, 128),
+                Color32::from_rgb(100, 80, 0),
+                Color32::from_rgb(0, 158, 115),
+                Color32::from_rgb(0, 0, 0),
+                Color32::from_rgb(110, 110, 110),
+            ],
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Palette {
+            name: "High Contrast".to_string(),
+            dark: true,
+            unrevealed: Color32::from_gray(200),
+            revealed: Color32::BLACK,
+            border: Color32::WHITE,
+            flag: Color32::from_rgb(255, 0, 0),
+            detonated: Color32::from_rgb(255, 255, 0),
+            misflagged: Color32::from_rgb(255, 0, 255),
+            numerals: [
+                Color32::from_rgb(0, 255, 255),
+                Color32::from_rgb(0, 255, 0),
+                Color32::from_rgb(255, 255, 0),
+                Color32::from_rgb(255, 0, 255),
+                Color32::from_rgb(255, 128, 0),
+                Color32::from_rgb(128, 192, 255),
+                Color32::WHITE,
+                Color32::from_gray(180),
+            ],
+        }
+    }
+
+    /// Resolves the palette to draw with for the selected theme.
+    ///
+    /// `system_dark` is the operating system's preference, if known. Unknown custom
+    /// themes fall back to the dark palette.
+    pub fn for_theme(theme: &VisualTheme, system_dark: Option<bool>, custom: &[Palette]) -> Self {
+        match theme {
+            VisualTheme::Dark => Palette::dark(),
+            VisualTheme::Light => Palette::light(),
+            VisualTheme::Deuteranopia => Palette::deuteranopia(),
+            VisualTheme::Protanopia => Palette::protanopia(),
+            VisualTheme::HighContrast => Palette::high_contrast(),
+            VisualTheme::System => {
+                if system_dark.unwrap_or(true) {
+                    Palette::dark()
+                } else {
+                    Palette::light()
+                }
+            }
+            VisualTheme::Custom(name) => custom
+                .iter()
+                .find(|p| &p.name == name)
+                .cloned()
+                .unwrap_or_else(Palette::dark),
+        }
+    }
+
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let t = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&t)?)
+    }
+
+    /// Loads every `*.toml` palette in the user's theme directory. Files that
+    /// fail to parse are reported and skipped.
+    pub fn load_custom_themes() -> Vec<Palette> {
+        // Themes are read from both the config directory and the legacy directory
+        let themes_dirs = [
+            config_dir().map(|d| d.join("themes")),
+            legacy_dir().map(|d| d.join("minesofrust-themes")),
+        ];
+
+        let mut entries = themes_dirs
+            .iter()
+            .flatten()
+            .filter_map(|d| std::fs::read_dir(d).ok())
+            .flat_map(|rd| {
+                rd.filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().map(|e| e == "toml").unwrap_or(false))
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        entries
+            .iter()
+            .filter_map(|p| match Palette::load_from_file(p) {
+                Ok(palette) => {
+                    println!("Loaded theme '{}' from {:?}", palette.name, p);
+                    Some(palette)
+                }
+                Err(why) => {
+                    println!("Failed to load theme from {:?}: {}", p, why);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+pub mod as_hex_color {
+    use egui::Color32;
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn to_hex(color: &Color32) -> String {
+        format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+    }
+
+    pub fn from_hex(s: &str) -> Result<Color32, String> {
+        let h = s.trim().trim_start_matches('#');
+        if h.len() != 6 {
+            return Err(format!("Invalid color '{}', expected #rrggbb", s));
+        }
+        let v = u32::from_str_radix(h, 16).map_err(|_| format!("Invalid color '{}'", s))?;
+        Ok(Color32::from_rgb(
+            (v >> 16 & 0xff) as u8,
+            (v >> 8 & 0xff) as u8,
+            (v & 0xff) as u8,
+        ))
+    }
+
+    pub fn serialize<S>(color: &Color32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&to_hex(color))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Color32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        from_hex(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+pub mod as_hex_colors {
+    use egui::Color32;
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    use super::as_hex_color::{from_hex, to_hex};
+
+    pub fn serialize<S>(colors: &[Color32; 8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(colors.iter().map(to_hex))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<[Color32; 8], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = Vec::<String>::deserialize(deserializer)?;
+        let colors = v
+            .iter()
+            .map(|s| from_hex(s))
+            .collect::<Result<Vec<Color32>, String>>()
+            .map_err(serde::de::Error::custom)?;
+        colors
+            .try_into()
+            .map_err(|_| serde::de::Error::custom("Expected exactly 8 numeral colors"))
+    }
+}
+
+#[test]
+fn test_palette_toml_round_trip() {
+    let palette = Palette::deuteranopia();
+    let s = toml::to_string(&palette).unwrap();
+    assert!(s.contains("unrevealed = \"#9999cc\""));
+    let reloaded: Palette = toml::from_str(&s).unwrap();
+    assert_eq!(reloaded, palette);
+}
+
+#[test]
+fn test_palette_invalid_colors() {
+    let mut s = toml::to_string(&Palette::dark()).unwrap();
+    s = s.replace("#005c80", "#05c80");
+    assert!(toml::from_str::<Palette>(&s).is_err());
+
+    let s = toml::to_string(&Palette::dark()).unwrap();
+    let s = s.replace("\"#353535\"]", "]");
+    assert!(toml::from_str::<Palette>(&s).is_err());
+}
+
+#[test]
+fn test_palette_for_theme() {
+    let mut custom = Palette::light();
+    custom.name = "Office".to_string();
+    let custom = vec![custom];
+
+    assert_eq!(
+        Palette::for_theme(&VisualTheme::System, Some(false), &custom).name,
+        "Light"
+    );
+    assert_eq!(
+        Palette::for_theme(&VisualTheme::System, None, &custom).name,
+        "Dark"
+    );
+    assert_eq!(
+        Palette::for_theme(&VisualTheme::Custom("Office".to_string()), None, &custom).name,
+        "Office"
+    );
+    assert_eq!(
+        Palette::for_theme(&VisualTheme::Custom("Missing".to_string()), None, &custom).name,
+        "Dark"
+    );
+}
@@ -0,0 +1,168 @@
+use crate::enums::GameState;
+use crate::minesweeper::Square;
+
+/// The visual state of a single square on the board.
+///
+/// Playing
+///      Unrevealed
+///      Unrevealed Flagged
+///      Unrevealed Questioned
+///      Revealed numeral
+///      Revealed blank
+///      Unrevealed, Mouse down, left button (Pressed)
+///      Unrevealed, Mouse down, chord (Pressed)
+/// Loss
+///      Unrevealed
+///      Unrevealed non-mined flagged (MisFlagged)
+///      Unrevealed mined flagged (FlagCorrect)
+///      Unrevealed mined, unflagged or questioned (Mine)
+///      Unrevealed non-mined questioned (Questioned)
+///      Revealed mined, losing play (Detonated)
+///      Revealed mined, adjacent to losing play (Blasted)
+///      Revealed numeral
+///      Revealed blank
+/// Win
+///      Unrevealed
+///      Unrevealed flagged
+///      Revealed numeral
+///      Revealed blank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Unrevealed,
+    Pressed,
+    Flagged,
+    Questioned,
+    Blank,
+    Numeral(u32),
+    Mine,
+    Detonated,
+    Blasted,
+    FlagCorrect,
+    MisFlagged,
+}
+
+impl Tile {
+    /// Determines how a square should be drawn given the state of the game.
+    pub fn for_square(
+        sqr: &Square,
+        game_state: &GameState,
+        is_detonated: bool,
+        is_pressed: bool,
+    ) -> Tile {
+        if *game_state == GameState::EndedLoss {
+            if is_detonated {
+                Tile::Detonated
+            } else if sqr.is_mine() && sqr.is_revealed {
+                Tile::Blasted
+            } else if sqr.is_mine() && sqr.is_flagged() {
+                Tile::FlagCorrect
+            } else if sqr.is_mine() {
+                Tile::Mine
+            } else if sqr.is_flagged() {
+                Tile::MisFlagged
+            } else if sqr.is_questioned() {
+                Tile::Questioned
+            } else if sqr.is_revealed {
+                Tile::revealed(sqr)
+            } else {
+                Tile::Unrevealed
+            }
+        } else if sqr.is_flagged() {
+            Tile::Flagged
+        } else if sqr.is_questioned() {
+            Tile::Questioned
+        } else if sqr.is_revealed {
+            Tile::revealed(sqr)
+        } else if is_pressed && !game_state.game_ended() {
+            Tile::Pressed
+        } else {
+            Tile::Unrevealed
+        }
+    }
+
+    fn revealed(sqr: &Square) -> Tile {
+        if sqr.numeral > 0 {
+            Tile::Numeral(sqr.numeral)
+        } else {
+            Tile::Blank
+        }
+    }
+}
+
+#[test]
+fn test_tile_playing() {
+    use crate::minesweeper::Marking;
+
+    let playing = GameState::Playing;
+    let mut sqr = Square::default();
+    assert_eq!(
+        Tile::for_square(&sqr, &playing, false, false),
+        Tile::Unrevealed
+    );
+    assert_eq!(Tile::for_square(&sqr, &playing, false, true), Tile::Pressed);
+
+    sqr.marking = Marking::Flag;
+    assert_eq!(Tile::for_square(&sqr, &playing, false, true), Tile::Flagged);
+
+    sqr.marking = Marking::Question;
+    assert_eq!(
+        Tile::for_square(&sqr, &playing, false, true),
+        Tile::Questioned
+    );
+
+    sqr.marking = Marking::None;
+    sqr.is_revealed = true;
+    assert_eq!(Tile::for_square(&sqr, &playing, false, true), Tile::Blank);
+
+    sqr.numeral = 3;
+    assert_eq!(
+        Tile::for_square(&sqr, &playing, false, false),
+        Tile::Numeral(3)
+    );
+}
+
+#[test]
+fn test_tile_loss() {
+    use crate::minesweeper::Marking;
+
+    let loss = GameState::EndedLoss;
+
+    let mut mine = Square::default_mine();
+    assert_eq!(Tile::for_square(&mine, &loss, false, false), Tile::Mine);
+    assert_eq!(Tile::for_square(&mine, &loss, true, false), Tile::Detonated);
+
+    mine.marking = Marking::Flag;
+    assert_eq!(
+        Tile::for_square(&mine, &loss, false, false),
+        Tile::FlagCorrect
+    );
+
+    mine.marking = Marking::None;
+    mine.is_revealed = true;
+    assert_eq!(Tile::for_square(&mine, &loss, false, false), Tile::Blasted);
+
+    let mut empty = Square::default();
+    assert_eq!(
+        Tile::for_square(&empty, &loss, false, true),
+        Tile::Unrevealed
+    );
+    empty.marking = Marking::Flag;
+    assert_eq!(
+        Tile::for_square(&empty, &loss, false, false),
+        Tile::MisFlagged
+    );
+}
+
+#[test]
+fn test_tile_win() {
+    use crate::minesweeper::Marking;
+
+    let win = GameState::EndedWin;
+    let mut mine = Square::default_mine();
+    mine.marking = Marking::Flag;
+    assert_eq!(Tile::for_square(&mine, &win, false, false), Tile::Flagged);
+    assert_eq!(
+        Tile::for_square(&Square::default(), &win, false, true),
+        Tile::Unrevealed
+    );
+}
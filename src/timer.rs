@@ -0,0 +1,175 @@
+use std::time::{Duration, Instant};
+
+/// Source of the current time. The game uses the monotonic system clock, while
+/// tests supply their own so they can control the passage of time.
+pub trait Clock: Clone {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonotonicClock;
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimerState {
+    Idle,
+    Running(Instant),
+    Paused,
+    Stopped,
+}
+
+/// Measures the playing time of a game. Time spent paused is not counted, and
+/// because the clock is monotonic, changes to the system clock have no effect.
+///
+/// Like the classic game, the timer is started by the first click rather than when
+/// the board is created.
+#[derive(Debug, Clone)]
+pub struct GameTimer<C: Clock = MonotonicClock> {
+    clock: C,
+    state: TimerState,
+
+    /// Time accumulated before the current running interval
+    accumulated: Duration,
+}
+
+impl Default for GameTimer<MonotonicClock> {
+    fn default() -> Self {
+        GameTimer::with_clock(MonotonicClock)
+    }
+}
+
+impl<C: Clock> GameTimer<C> {
+    pub fn with_clock(clock: C) -> Self {
+        GameTimer {
+            clock,
+            state: TimerState::Idle,
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    /// Starts timing a new game. Has no effect if the timer has already started.
+    pub fn start(&mut self) {
+        if self.state == TimerState::Idle {
+            self.state = TimerState::Running(self.clock.now());
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if let TimerState::Running(since) = self.state {
+            self.accumulated += self.clock.now() - since;
+            self.state = TimerState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state == TimerState::Paused {
+            self.state = TimerState::Running(self.clock.now());
+        }
+    }
+
+    /// Stops the timer at the end of a game and returns the final time
+    pub fn stop(&mut self) -> Duration {
+        self.pause();
+        self.state = TimerState::Stopped;
+        self.accumulated
+    }
+
+    /// Returns the timer to its initial, unstarted, state
+    pub fn reset(&mut self) {
+        self.state = TimerState::Idle;
+        self.accumulated = Duration::ZERO;
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self.state {
+            TimerState::Running(since) => self.accumulated + (self.clock.now() - since),
+            _ => self.accumulated,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, TimerState::Running(_))
+    }
+}
+
+/// Formats a time in seconds the same way everywhere it's shown
+pub fn format_time(seconds: f64) -> String {
+    format!("{:.2}", seconds)
+}
+
+#[cfg(test)]
+use std::{cell::Cell, rc::Rc};
+
+/// A clock that only moves when told to
+#[cfg(test)]
+#[derive(Debug, Clone)]
+struct ManualClock {
+    base: Instant,
+    offset: Rc<Cell<Duration>>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    fn new() -> Self {
+        ManualClock {
+            base: Instant::now(),
+            offset: Rc::new(Cell::new(Duration::ZERO)),
+        }
+    }
+
+    fn advance(&self, seconds: u64) {
+        self.offset
+            .set(self.offset.get() + Duration::from_secs(seconds));
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + self.offset.get()
+    }
+}
+
+#[test]
+fn test_timer_pause_semantics() {
+    let clock = ManualClock::new();
+    let mut timer = GameTimer::with_clock(clock.clone());
+
+    // Not counting until the first click
+    clock.advance(5);
+    assert_eq!(timer.elapsed(), Duration::ZERO);
+
+    timer.start();
+    clock.advance(3);
+    assert_eq!(timer.elapsed(), Duration::from_secs(3));
+    assert!(timer.is_running());
+
+    timer.pause();
+    clock.advance(100);
+    assert_eq!(timer.elapsed(), Duration::from_secs(3));
+
+    // Starting again mid-game doesn't restart the timer
+    timer.resume();
+    timer.start();
+    clock.advance(2);
+    assert_eq!(timer.elapsed(), Duration::from_secs(5));
+
+    assert_eq!(timer.stop(), Duration::from_secs(5));
+    clock.advance(10);
+    assert_eq!(timer.elapsed(), Duration::from_secs(5));
+    assert!(!timer.is_running());
+
+    timer.reset();
+    assert_eq!(timer.elapsed(), Duration::ZERO);
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_time(0.0), "0.00");
+    assert_eq!(format_time(12.3456), "12.35");
+}
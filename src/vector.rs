@@ -0,0 +1,112 @@
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Shape, Stroke, Vec2};
+
+// Vector drawn board tiles. Everything is sized relative to the square so that
+// it looks the same at any square size or display scale.
+
+/// Maps a position given as fractions of the square onto the square
+fn at(rect: Rect, fx: f32, fy: f32) -> Pos2 {
+    rect.min + Vec2::new(rect.width() * fx, rect.height() * fy)
+}
+
+/// Draws a numeral using the UI font. In large mode the numeral fills most of
+/// the square and is emboldened.
+pub fn paint_numeral(painter: &Painter, rect: Rect, numeral: u32, color: Color32, large: bool) {
+    let (size, weight) = if large {
+        (rect.height() * 0.95, rect.height() * 0.03)
+    } else {
+        (rect.height() * 0.7, rect.height() * 0.015)
+    };
+    let text = format!("{}", numeral);
+    let font = FontId::proportional(size);
+
+    // egui's default fonts have no bold weight, so thicken by overdrawing
+    [-weight, 0.0, weight].iter().for_each(|dx| {
+        painter.text(
+            rect.center() + Vec2::new(*dx, 0.0),
+            Align2::CENTER_CENTER,
+            &text,
+            font.clone(),
+            color,
+        );
+    });
+}
+
+pub fn paint_flag(painter: &Painter, rect: Rect, color: Color32) {
+    let w = rect.width();
+    let pole = Color32::from_gray(20);
+
+    painter.add(Shape::convex_polygon(
+        vec![
+            at(rect, 0.58, 0.18),
+            at(rect, 0.58, 0.52),
+            at(rect, 0.2, 0.35),
+        ],
+        color,
+        Stroke::NONE,
+    ));
+    painter.line_segment(
+        [at(rect, 0.58, 0.18), at(rect, 0.58, 0.8)],
+        Stroke::new(w * 0.06, pole),
+    );
+    painter.rect_filled(
+        Rect::from_min_max(at(rect, 0.3, 0.76), at(rect, 0.8, 0.84)),
+        w * 0.02,
+        pole,
+    );
+}
+
+pub fn paint_mine(painter: &Painter, rect: Rect) {
+    let w = rect.width();
+    let c = rect.center();
+    let body = Color32::from_gray(10);
+    let stroke = Stroke::new(w * 0.06, body);
+
+    painter.line_segment([at(rect, 0.5, 0.12), at(rect, 0.5, 0.88)], stroke);
+    painter.line_segment([at(rect, 0.12, 0.5), at(rect, 0.88, 0.5)], stroke);
+    painter.line_segment([at(rect, 0.24, 0.24), at(rect, 0.76, 0.76)], stroke);
+    painter.line_segment([at(rect, 0.24, 0.76), at(rect, 0.76, 0.24)], stroke);
+    painter.circle_filled(c, w * 0.26, body);
+    painter.circle_filled(c - Vec2::splat(w * 0.08), w * 0.06, Color32::WHITE);
+}
+
+/// The marker drawn over an incorrectly placed flag
+pub fn paint_cross(painter: &Painter, rect: Rect) {
+    let stroke = Stroke::new(rect.width() * 0.08, Color32::from_rgb(200, 0, 0));
+    painter.line_segment([at(rect, 0.18, 0.18), at(rect, 0.82, 0.82)], stroke);
+    painter.line_segment([at(rect, 0.18, 0.82), at(rect, 0.82, 0.18)], stroke);
+}
+
+/// The marker drawn over a correctly placed flag
+pub fn paint_check(painter: &Painter, rect: Rect) {
+    painter.add(Shape::line(
+        vec![
+            at(rect, 0.2, 0.55),
+            at(rect, 0.4, 0.76),
+            at(rect, 0.8, 0.26),
+        ],
+        Stroke::new(rect.width() * 0.1, Color32::from_rgb(0, 150, 0)),
+    ));
+}
+
+pub fn paint_question(painter: &Painter, rect: Rect) {
+    painter.text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        "?",
+        FontId::proportional(rect.height() * 0.75),
+        Color32::from_gray(20),
+    );
+}
+
+/// Raised edges on an unrevealed square
+pub fn paint_bevel(painter: &Painter, rect: Rect) {
+    let inset = rect.width() * 0.04;
+    let r = rect.shrink(inset);
+    let light = Stroke::new(inset * 2.0, Color32::from_white_alpha(90));
+    let dark = Stroke::new(inset * 2.0, Color32::from_black_alpha(90));
+
+    painter.line_segment([r.left_bottom(), r.left_top()], light);
+    painter.line_segment([r.left_top(), r.right_top()], light);
+    painter.line_segment([r.right_top(), r.right_bottom()], dark);
+    painter.line_segment([r.right_bottom(), r.left_bottom()], dark);
+}
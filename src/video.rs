@@ -0,0 +1,492 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::boardcode::MAX_DIMENSION;
+use crate::minesweeper::{Coordinate, RevealType, Variant};
+use crate::replay::{Replay, ReplayMove, MAX_MOVE_TIME};
+
+/// The size, in pixels, of a square in the mouse positions Arbiter records
+const AVF_SQUARE_PIXELS: u32 = 16;
+
+/// How many records in a row must decode as events on the board before the search
+/// for the start of an AVF's events settles on a position
+const AVF_EVENT_RUN: usize = 3;
+
+/// The formats of replays ("videos") recorded by other minesweeper clients, chosen by
+/// file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFormat {
+    /// The RAW Video Format, plain text that most clients can export
+    Rawvf,
+
+    /// Minesweeper Arbiter's binary videos
+    Avf,
+}
+
+impl VideoFormat {
+    pub fn for_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("rawvf") => Ok(VideoFormat::Rawvf),
+            Some("avf") => Ok(VideoFormat::Avf),
+            _ => Err(anyhow!("Videos must end in .rawvf or .avf")),
+        }
+    }
+}
+
+/// A game recorded by another client, as a replay of our own
+#[derive(Debug, Clone, PartialEq)]
+pub struct Video {
+    pub replay: Replay,
+    pub player: Option<String>,
+    pub program: Option<String>,
+}
+
+/// The mouse events recorded in a video, named as in RAWVF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mouse {
+    Move,
+    LeftDown,
+    LeftUp,
+    RightDown,
+    RightUp,
+    MiddleDown,
+    MiddleUp,
+}
+
+impl Mouse {
+    fn from_rawvf(code: &str) -> Option<Self> {
+        match code {
+            "mv" => Some(Mouse::Move),
+            "lc" => Some(Mouse::LeftDown),
+            "lr" => Some(Mouse::LeftUp),
+            "rc" => Some(Mouse::RightDown),
+            "rr" => Some(Mouse::RightUp),
+            "mc" | "sc" => Some(Mouse::MiddleDown),
+            "mr" => Some(Mouse::MiddleUp),
+            _ => None,
+        }
+    }
+
+    fn from_avf(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Mouse::Move),
+            3 => Some(Mouse::LeftDown),
+            5 | 21 => Some(Mouse::LeftUp),
+            9 => Some(Mouse::RightDown),
+            17 | 145 => Some(Mouse::RightUp),
+            33 | 11 => Some(Mouse::MiddleDown),
+            65 | 193 => Some(Mouse::MiddleUp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MouseEvent {
+    time: f64,
+    mouse: Mouse,
+
+    /// The square under the pointer. None if it's off the board.
+    square: Option<Coordinate>,
+}
+
+/// Reads a video, in the format given by its file extension
+pub fn load_video(path: &Path) -> Result<Video> {
+    match VideoFormat::for_path(path)? {
+        VideoFormat::Rawvf => from_rawvf(&std::fs::read_to_string(path)?),
+        VideoFormat::Avf => from_avf(&std::fs::read(path)?),
+    }
+}
+
+/// Parses a RAWVF video: `Key: value` header lines, then the board after `Board:`
+/// with `*` for each mine, then one event per line, such as `1.23 lc 40 24 (3 2)`,
+/// where the bracketed square is counted from 1.
+pub fn from_rawvf(text: &str) -> Result<Video> {
+    let mut lines = text.lines().map(|l| l.trim());
+    let mut header = vec![];
+    for line in lines.by_ref() {
+        if line.eq_ignore_ascii_case("Board:") {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            header.push((key.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    let field = |key: &str| {
+        header
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+    let dimension = |key: &str| -> Result<u32> {
+        field(key)
+            .ok_or(anyhow!("The video has no {}", key))?
+            .parse::<u32>()
+            .map_err(|_| anyhow!("Invalid {}", key))
+    };
+    let (width, height) = (dimension("width")?, dimension("height")?);
+    check_size(width, height)?;
+
+    let mut mines = vec![];
+    for _ in 0..height {
+        let row = lines.next().ok_or(anyhow!("The board is incomplete"))?;
+        if row.chars().count() != width as usize {
+            return Err(anyhow!("The board's rows aren't {} squares wide", width));
+        }
+        mines.extend(row.chars().map(|c| c == '*'));
+    }
+
+    let events = lines
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+            let mouse = Mouse::from_rawvf(fields.get(1)?)?;
+            let time = fields[0].parse::<f64>().ok()?;
+            let square = match fields.as_slice() {
+                [_, _, _, _, col, row, ..] => {
+                    let col = col.trim_start_matches('(').parse::<u32>().ok()?;
+                    let row = row.trim_end_matches(')').parse::<u32>().ok()?;
+                    on_board(col.checked_sub(1)?, row.checked_sub(1)?, width, height)
+                }
+                [_, _, x, y] => pixel_square(x.parse().ok()?, y.parse().ok()?, width, height),
+                _ => None,
+            };
+            Some(MouseEvent {
+                time,
+                mouse,
+                square,
+            })
+        })
+        .collect::<Vec<MouseEvent>>();
+    if let Some(e) = events
+        .iter()
+        .find(|e| !(0.0..=MAX_MOVE_TIME).contains(&e.time))
+    {
+        return Err(anyhow!("Invalid event time {}", e.time));
+    }
+
+    let mut replay = replay_of(width, height, mines, &events)?;
+    replay.use_question_marks = field("marks").is_some_and(|m| m.eq_ignore_ascii_case("on"));
+    Ok(Video {
+        replay,
+        player: field("player"),
+        program: field("program"),
+    })
+}
+
+/// Parses an Arbiter video. After a version byte and four unused bytes comes the
+/// level: 3, 4 and 5 for the standard boards, or 6 for a custom board followed by its
+/// width and height less one and a big-endian mine count. Then the mines, each as a
+/// row and column counted from 1, and a bracketed text header. The events, eight
+/// bytes each, follow the header after some data this doesn't need, so they're found
+/// by searching for records that decode as events.
+pub fn from_avf(bytes: &[u8]) -> Result<Video> {
+    let truncated = || anyhow!("The video is truncated");
+    let byte = |i: usize| bytes.get(i).copied().ok_or_else(truncated);
+
+    let (width, height, num_mines, mut pos) = match byte(5)? {
+        3 => (8, 8, 10, 6),
+        4 => (16, 16, 40, 6),
+        5 => (30, 16, 99, 6),
+        6 => (
+            byte(6)? as u32 + 1,
+            byte(7)? as u32 + 1,
+            (byte(8)? as u32) << 8 | byte(9)? as u32,
+            10,
+        ),
+        level => return Err(anyhow!("Unknown level {}", level)),
+    };
+    check_size(width, height)?;
+
+    let mut mines = vec![false; (width * height) as usize];
+    for _ in 0..num_mines {
+        let (row, col) = (byte(pos)? as u32, byte(pos + 1)? as u32);
+        pos += 2;
+        let square = on_board(col.wrapping_sub(1), row.wrapping_sub(1), width, height)
+            .ok_or(anyhow!("A mine is off the board"))?;
+        mines[(square.y * width + square.x) as usize] = true;
+    }
+
+    let header_end = bytes[pos..]
+        .iter()
+        .position(|b| *b == b']')
+        .map(|i| pos + i + 1)
+        .ok_or_else(truncated)?;
+    let start = (header_end..bytes.len())
+        .find(|start| {
+            let records = (0..AVF_EVENT_RUN)
+                .map(|i| avf_event(bytes, start + i * 8, width, height))
+                .collect::<Option<Vec<MouseEvent>>>();
+            records.is_some_and(|r| r[0].time < 1.0 && r.iter().all(|e| e.square.is_some()))
+        })
+        .ok_or(anyhow!("The video has no events"))?;
+
+    let events = (start..bytes.len())
+        .step_by(8)
+        .map_while(|i| avf_event(bytes, i, width, height))
+        .collect::<Vec<MouseEvent>>();
+
+    Ok(Video {
+        replay: replay_of(width, height, mines, &events)?,
+        player: None,
+        program: Some("Arbiter".to_string()),
+    })
+}
+
+/// Decodes the eight-byte event at `pos`: the mouse code, then the pointer position
+/// and time with their bytes interleaved. None if it isn't a valid event.
+fn avf_event(bytes: &[u8], pos: usize, width: u32, height: u32) -> Option<MouseEvent> {
+    let b = bytes.get(pos..pos + 8)?;
+    let mouse = Mouse::from_avf(b[0])?;
+    let x = (b[1] as u32) << 8 | b[3] as u32;
+    let y = (b[5] as u32) << 8 | b[7] as u32;
+    let seconds = ((b[6] as u32) << 8 | b[2] as u32).checked_sub(1)?;
+    if b[4] >= 100 {
+        return None;
+    }
+    Some(MouseEvent {
+        time: seconds as f64 + b[4] as f64 / 100.0,
+        mouse,
+        square: pixel_square(x, y, width, height),
+    })
+}
+
+fn check_size(width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+        Err(anyhow!("Invalid board size {}x{}", width, height))
+    } else {
+        Ok(())
+    }
+}
+
+fn on_board(x: u32, y: u32, width: u32, height: u32) -> Option<Coordinate> {
+    (x < width && y < height).then_some(Coordinate { x, y })
+}
+
+fn pixel_square(x: u32, y: u32, width: u32, height: u32) -> Option<Coordinate> {
+    on_board(x / AVF_SQUARE_PIXELS, y / AVF_SQUARE_PIXELS, width, height)
+}
+
+/// The replay of a recorded game. The board is kept as its layout, since it wasn't
+/// placed by our generator.
+fn replay_of(width: u32, height: u32, mines: Vec<bool>, events: &[MouseEvent]) -> Result<Replay> {
+    let num_mines = mines.iter().filter(|m| **m).count() as u32;
+    if num_mines == 0 || num_mines >= width * height {
+        return Err(anyhow!("The board has no mines or no safe squares"));
+    }
+    let moves = moves_from_events(events);
+    let first_click = moves
+        .iter()
+        .find(|m| m.play_type != RevealType::Flag)
+        .map(|m| m.coord.clone())
+        .unwrap_or(Coordinate { x: 0, y: 0 });
+
+    Ok(Replay {
+        width,
+        height,
+        num_mines,
+        seed: 0,
+        first_click,
+        layout: Some(mines),
+        use_question_marks: false,
+        question_blocks_reveal: false,
+        variant: Variant::default(),
+        moves,
+    })
+}
+
+/// Turns mouse events into plays the way the classic clients do: a right press flags,
+/// a left release reveals, and releasing either button while both are held, or
+/// releasing the middle button, chords. Events off the board make no play, and times
+/// before the game started count as its start.
+fn moves_from_events(events: &[MouseEvent]) -> Vec<ReplayMove> {
+    let (mut left, mut right, mut chording) = (false, false, false);
+    let mut moves = vec![];
+    let mut play = |e: &MouseEvent, play_type: RevealType| {
+        if let Some(coord) = &e.square {
+            moves.push(ReplayMove {
+                time: e.time.max(0.0),
+                coord: coord.clone(),
+                play_type,
+            });
+        }
+    };
+
+    for e in events {
+        match e.mouse {
+            Mouse::Move | Mouse::MiddleDown => {}
+            Mouse::LeftDown => {
+                left = true;
+                chording |= right;
+            }
+            Mouse::RightDown => {
+                right = true;
+                if left {
+                    chording = true;
+                } else {
+                    play(e, RevealType::Flag);
+                }
+            }
+            Mouse::LeftUp | Mouse::RightUp => {
+                if left && right {
+                    play(e, RevealType::Chord);
+                } else if e.mouse == Mouse::LeftUp && !chording {
+                    play(e, RevealType::Reveal);
+                }
+                if e.mouse == Mouse::LeftUp {
+                    left = false;
+                } else {
+                    right = false;
+                }
+                chording &= left || right;
+            }
+            Mouse::MiddleUp => play(e, RevealType::Chord),
+        }
+    }
+    moves
+}
+
+#[cfg(test)]
+use crate::replay::{ReplayOutcome, SMALL_TEST_MINES};
+
+#[test]
+fn test_rawvf() -> Result<()> {
+    let text = "RawVF_Version: Rev2
+Program: Vienna MineSweeper
+Player: Jane Doe
+Width: 4
+Height: 3
+Mines: 2
+Marks: Off
+Board:
+...*
+....
+...*
+Events:
+0.00 start
+0.00 lc 8 8 (1 1)
+0.05 lr 8 8 (1 1)
+0.60 mv 60 8 (4 1)
+0.80 rc 56 8 (4 1)
+0.90 rr 56 8 (4 1)
+1.20 lc 40 8 (3 1)
+1.25 rc 40 8 (3 1)
+1.30 rr 40 8 (3 1)
+1.35 lr 40 8 (3 1)
+1.35 won
+";
+    let video = from_rawvf(text)?;
+    assert_eq!(video.player.as_deref(), Some("Jane Doe"));
+    assert_eq!(video.program.as_deref(), Some("Vienna MineSweeper"));
+
+    let replay = &video.replay;
+    assert_eq!((replay.width, replay.height, replay.num_mines), (4, 3, 2));
+    let layout = replay.layout.as_ref().unwrap();
+    assert_eq!(
+        SMALL_TEST_MINES
+            .iter()
+            .map(|i| layout[*i])
+            .collect::<Vec<bool>>(),
+        [true, true]
+    );
+    assert_eq!(
+        replay
+            .moves
+            .iter()
+            .map(|m| (m.coord.x, m.coord.y, m.play_type.clone()))
+            .collect::<Vec<_>>(),
+        [
+            (0, 0, RevealType::Reveal),
+            (3, 0, RevealType::Flag),
+            (2, 0, RevealType::Chord),
+        ]
+    );
+    assert_eq!(replay.moves[2].time, 1.3);
+
+    // Revealing the top left square opens all but the right column, and chording the
+    // flagged mine's neighbour clears the rest
+    let stats = replay.analyse()?;
+    assert_eq!(stats.outcome, ReplayOutcome::Won);
+    assert_eq!((stats.three_bv, stats.clicks, stats.flags_used), (2, 3, 1));
+    assert_eq!(stats.time, 1.3);
+    assert!(replay.verify(1.35).is_ok());
+
+    // A code can carry the imported board
+    assert_eq!(Replay::from_code(&replay.to_code())?, *replay);
+
+    assert!(from_rawvf("Width: 4\nHeight: 3\nBoard:\n...*\n").is_err());
+    assert!(from_rawvf("Width: 4\nBoard:\n...*\n").is_err());
+    assert!(from_rawvf("Width: 4\nHeight: 1\nBoard:\n....\n").is_err());
+    let late = text.replace("1.35 lr", "1e300 lr");
+    assert!(from_rawvf(&late).is_err());
+    let early = text.replace("0.60 mv", "-1.0 mv");
+    assert!(from_rawvf(&early).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_avf() -> Result<()> {
+    // Seconds are stored plus one, split around the hundredths
+    let event = |code: u8, x: u32, y: u32, time: f64| {
+        let seconds = time as u32 + 1;
+        let hundredths = ((time - time.floor()) * 100.0).round() as u8;
+        [
+            code,
+            (x >> 8) as u8,
+            seconds as u8,
+            x as u8,
+            hundredths,
+            (y >> 8) as u8,
+            (seconds >> 8) as u8,
+            y as u8,
+        ]
+    };
+
+    let mut bytes = vec![0x03, 0, 0, 0, 0, 6, 3, 2, 0, 2, 1, 4, 3, 4];
+    bytes.extend(b"[0|14.02.2024|Jane]");
+    bytes.extend([0x11; 5]);
+    [
+        event(3, 8, 8, 0.0),
+        event(5, 8, 8, 0.05),
+        event(9, 56, 8, 0.8),
+        event(17, 56, 8, 0.9),
+        event(33, 40, 8, 1.25),
+        event(65, 40, 8, 1.3),
+    ]
+    .iter()
+    .for_each(|e| bytes.extend(e));
+    bytes.extend([0; 8]);
+
+    let video = from_avf(&bytes)?;
+    let replay = &video.replay;
+    assert_eq!((replay.width, replay.height, replay.num_mines), (4, 3, 2));
+    let layout = replay.layout.as_ref().unwrap();
+    assert!(SMALL_TEST_MINES.iter().all(|i| layout[*i]));
+    assert_eq!(replay.moves.len(), 3);
+    assert_eq!(replay.moves[1].play_type, RevealType::Flag);
+    assert_eq!(replay.moves[2].play_type, RevealType::Chord);
+    assert_eq!(replay.analyse()?.outcome, ReplayOutcome::Won);
+
+    assert!(from_avf(&bytes[..12]).is_err());
+    let mut unknown_level = bytes.clone();
+    unknown_level[5] = 9;
+    assert!(from_avf(&unknown_level).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_video_format() {
+    assert_eq!(
+        VideoFormat::for_path(Path::new("run.RAWVF")).unwrap(),
+        VideoFormat::Rawvf
+    );
+    assert_eq!(
+        VideoFormat::for_path(Path::new("run.avf")).unwrap(),
+        VideoFormat::Avf
+    );
+    assert!(VideoFormat::for_path(Path::new("run.mvf")).is_err());
+}